@@ -1,40 +1,62 @@
 use std::rc::Rc;
 
 use wgpu::{
-    Color, CommandEncoderDescriptor, Device, 
-    LoadOp, Operations, Queue, 
-    RenderPassColorAttachment, RenderPassDescriptor, 
-    RenderPipeline, StoreOp, Surface, 
-    SurfaceConfiguration, SurfaceError, TextureViewDescriptor
+    Color, CommandEncoderDescriptor, Device,
+    LoadOp, Operations, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, StoreOp, Surface,
+    SurfaceConfiguration, SurfaceError, TextureView,
+    TextureViewDescriptor
 };
 use winit::{
-    dpi::PhysicalSize,
+    dpi::{ PhysicalPosition, PhysicalSize },
     event::{
-        Event, WindowEvent
+        ElementState, Event, KeyEvent, MouseButton, WindowEvent
     },
     event_loop::EventLoop,
+    keyboard::{ KeyCode, PhysicalKey },
     window::Window,
 };
 
 use crate::{
-    entity::EntityList, utils
+    camera::CameraMode,
+    entity::{ Entity, EntityList }, 
+    input::{ self, InputState },
+    image::{ self, ImageFormat }, 
+    recording::{ Recorder, Recording },
+    timestep::{ FixedTimestep, FrameTime },
+    utils::{ self, defaults::Float, Vector }
 };
 
+// Zoom factor of one notch of the scroll wheel
+const SCROLL_ZOOM: Float = 1.1;
+// Factor the time scale changes by with - and =
+const TIME_SCALE_STEP: Float = 2.0;
+
 pub struct BaseRenderer<'a, T> {
-    surface: Surface<'a>,
-    window: &'a Window,
+    // Both are `None` when rendering headlessly
+    surface: Option<Surface<'a>>,
+    window: Option<&'a Window>,
     queue: Rc<Queue>,
     config: SurfaceConfiguration,
     device: Rc<Device>,
     size: PhysicalSize<u32>,
     render_pipeline: RenderPipeline,
-    pub(crate) entities: EntityList, 
+    pub(crate) entities: EntityList,
     multisample_texture: wgpu::Texture,
+    // Resolve target used in place of the surface when rendering headlessly
+    offscreen_texture: Option<wgpu::Texture>,
+    // Kept between captures of a windowed renderer, and replaced when the size changes
+    capture_texture: Option<wgpu::Texture>,
+    readback_buffer: Option<wgpu::Buffer>,
     // main_loop: Option<&'a mut dyn FnMut(&'a mut EntityList) -> ()>,
     main_loop: Option<T>,
+    timestep: FixedTimestep,
+    recorder: Option<Recorder>,
+    input: InputState,
 }
 
-impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
+impl<'a, T: for<'b> FnMut(&'b mut EntityList, &'b InputState, FrameTime)> BaseRenderer<'a, T> {
     pub async fn new(window: &'a Window) -> Self {
         let size = window.inner_size();
 
@@ -50,24 +72,12 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
         let adapter = instance
             .enumerate_adapters(wgpu::Backends::all())
             .into_iter()
-            .filter(|adapter| {
+            .find(|adapter| {
                 adapter.is_surface_supported(&surface)
             })
-            .next()
             .unwrap();
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    label: None,
-                    ..Default::default()
-                },
-                None, // Trace path
-            )
-            .await
-            .unwrap();
+        let (device, queue) = Self::request_device(&adapter, wgpu::Limits::default()).await.unwrap();
 
         let surface_capabilities = surface.get_capabilities(&adapter);
 
@@ -76,8 +86,7 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
             .iter()
             .copied()
             // Check for srgb texture format support
-            .filter(|format| format.is_srgb())
-            .next()
+            .find(|format| format.is_srgb())
             // If none is available, fallback to a format that is
             .unwrap_or(surface_capabilities.formats[0]);
 
@@ -95,25 +104,88 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
 
         surface.configure(&device, &config);
 
+        Self::from_parts(device, queue, config, Some(surface), Some(window))
+    }
+
+    /// Creates a renderer without a window, drawing into an offscreen texture instead of a surface.
+    /// Any adapter will do, including a software / fallback one. Returns `None` if there is none.
+    pub async fn new_headless(width: u32, height: u32) -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let mut adapter_options = wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        };
+
+        let adapter = match instance.request_adapter(&adapter_options).await {
+            Some(adapter) => adapter,
+            None => {
+                adapter_options.force_fallback_adapter = true;
+                instance.request_adapter(&adapter_options).await?
+            }
+        };
+
+        // Downlevel limits so that software adapters are accepted too
+        let limits = wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits());
+        let (device, queue) = Self::request_device(&adapter, limits).await.ok()?;
+
+        // Never handed to a surface, only used to keep track of the target's size and format
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        Some(Self::from_parts(device, queue, config, None, None))
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter, required_limits: wgpu::Limits) -> Result<(Device, Queue), wgpu::RequestDeviceError> {
+        adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features: wgpu::Features::empty(),
+                    required_limits,
+                    label: None,
+                },
+                None, // Trace path
+            )
+            .await
+    }
+
+    fn from_parts(
+        device: Device,
+        queue: Queue,
+        config: SurfaceConfiguration,
+        surface: Option<Surface<'a>>,
+        window: Option<&'a Window>
+    ) -> Self {
+        let size = PhysicalSize::new(config.width, config.height);
+
         let shader = device.create_shader_module(wgpu::include_wgsl!("base_shader.wgsl"));
-    
+
         let render_pipeline = utils::generate_render_pipeline(&device, config.format, shader);
 
-        let multisample_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Multisample texture"),
-            size: wgpu::Extent3d { width: size.width, height: size.height, depth_or_array_layers: 1 },
-            mip_level_count: 1,
-            sample_count: 4,
-            dimension: wgpu::TextureDimension::D2,
-            format: config.format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &vec![],
-        });
+        let multisample_texture = Self::multisample_texture(&device, &config);
+
+        let offscreen_texture = match surface {
+            Some(_) => None,
+            None => Some(Self::offscreen_texture(&device, &config)),
+        };
 
         let device = Rc::from(device);
         let queue = Rc::from(queue);
 
-        let entities = EntityList::new(device.clone(), queue.clone());
+        let mut entities = EntityList::new(device.clone(), queue.clone(), config.format);
+        entities.camera.set_screen_size(size.width, size.height);
 
         Self {
             window,
@@ -125,41 +197,150 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
             render_pipeline,
             entities,
             multisample_texture,
+            offscreen_texture,
+            capture_texture: None,
+            readback_buffer: None,
             main_loop: None,
+            timestep: FixedTimestep::new(1.0 / 60.0, 8),
+            recorder: None,
+            input: InputState::new(),
         }
     }
 
+    pub fn entities(&mut self) -> &mut EntityList {
+        &mut self.entities
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// The main loop is called once per frame, with the input since the last frame
+    /// and the amount of fixed steps to advance the simulation by
     pub fn set_main_loop(&mut self, main_loop: T) {
         self.main_loop = Some(main_loop);
     }
 
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// For scripting input when there is no window to take it from
+    pub fn input_mut(&mut self) -> &mut InputState {
+        &mut self.input
+    }
+
+    /// Sets the simulated time the clock carries on from, e.g. when resuming a snapshot
+    pub fn set_time(&mut self, time: f64) {
+        self.timestep.set_time(time);
+    }
+
+    /// Sets the length of a simulation step in seconds. Steps that aren't positive and finite are ignored
+    pub fn set_timestep(&mut self, dt: Float) {
+        self.timestep.set_dt(dt);
+    }
+
+    /// Sets the most steps a single frame may take, slow frames past that slow the simulation down instead
+    pub fn set_max_substeps(&mut self, max_substeps: u32) {
+        self.timestep.set_max_substeps(max_substeps);
+    }
+
+    pub fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.timestep.paused()
+    }
+
+    /// Stops advancing the simulation, frames are still drawn and the main loop still called, with no steps
+    pub fn pause(&mut self) {
+        self.timestep.set_paused(true);
+    }
+
+    pub fn resume(&mut self) {
+        self.timestep.set_paused(false);
+    }
+
+    /// Pauses if running, and has the next frame take exactly one step
+    pub fn step_once(&mut self) {
+        self.timestep.step_once();
+    }
+
+    pub fn time_scale(&self) -> Float {
+        self.timestep.time_scale()
+    }
+
+    /// Simulated seconds per real second, 2 takes twice the steps per frame. Also applies while recording
+    pub fn set_time_scale(&mut self, time_scale: Float) {
+        self.timestep.set_time_scale(time_scale);
+    }
+
+    /// Starts recording the frames drawn by `run`. Until stopped, every frame advances the simulation
+    /// by exactly one step, so the output doesn't depend on the real frame rate
+    pub fn start_recording(&mut self, recording: Recording) -> std::io::Result<()> {
+        self.stop_recording()?;
+        self.recorder = Some(Recorder::start(recording)?);
+        Ok(())
+    }
+
+    /// Stops recording and waits for the encoder, if any. Returns the amount of frames written
+    pub fn stop_recording(&mut self) -> std::io::Result<u64> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(0),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
     pub fn run(&mut self, event_loop: EventLoop<()>) {
         use coarsetime::Instant;
 
+        let window = self.window.expect("Headless renderers have no window to run, use `render_offscreen` instead");
+
         let mut frames = 0u64;
-        let mut fps = 0.6f32;
         let mut time = Instant::now();
-        
+        let mut last_frame = Instant::now();
+        let mut screenshots = 0u32;
+        let mut cursor: Option<PhysicalPosition<f64>> = None;
+        // Panning with the right button, the left one is left to the simulation
+        let mut panning = false;
+
         event_loop
             .run(move |event, window_target| {
+                // Everything the main loop may want to know about, the renderer's own controls below still apply
+                if let Event::WindowEvent { event: window_event, .. } = &event {
+                    self.input.handle_event(window_event);
+                }
+
                 match event {
                     Event::WindowEvent {
                         event: WindowEvent::CloseRequested,
                         ..
                     } => {
                         println!("Closing...");
+                        if let Err(e) = self.stop_recording() {
+                            println!("Failed to finish recording: {:?}", e);
+                        }
                         window_target.exit();
                     }
 
                     Event::AboutToWait => {
-                        self.window.request_redraw();
+                        window.request_redraw();
                     }
 
                     // Rendering, updation
                     Event::WindowEvent {
                         event: WindowEvent::RedrawRequested,
                         ..
-                    } => match self.render() {
+                    } => match self.render({
+                        let frame_time = last_frame.elapsed().as_f64() as Float;
+                        last_frame = Instant::now();
+                        frame_time
+                    }) {
                         Err(SurfaceError::Lost) => {
                             self.resize(self.size);
                         }
@@ -171,11 +352,17 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
                             println!("An error has occured when trying to render: {:?}", e);
                         }
                         Ok(()) => {
+                            if let Err(e) = self.record_frame() {
+                                println!("Recording failed, stopping: {:?}", e);
+                                // The recorder is dropped either way
+                                let _ = self.stop_recording();
+                            }
+
                             frames += 1;
                             if frames > 200 {
                                 let elapsed = time.elapsed().as_micros();
-                                fps = 1000000.0 * (frames as f32 / elapsed as f32);
-                                self.window.set_title(format!("FPS: {}", fps).as_str());
+                                let fps = 1000000.0 * (frames as f32 / elapsed as f32);
+                                window.set_title(format!("FPS: {}", fps).as_str());
                                 frames = 0;
                                 time = Instant::now();
                             }
@@ -188,6 +375,147 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
                         ..
                     } => self.resize(new_size),
 
+                    // Screenshot
+                    Event::WindowEvent {
+                        event: WindowEvent::KeyboardInput {
+                            event: KeyEvent {
+                                physical_key: PhysicalKey::Code(KeyCode::F12),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                            ..
+                        },
+                        ..
+                    } => {
+                        let path = format!("screenshot-{}-{}.png", unix_timestamp(), screenshots);
+                        match self.save_frame(&path) {
+                            Ok(()) => println!("Saved screenshot to {}", path),
+                            Err(e) => println!("Failed to save screenshot: {:?}", e),
+                        }
+                        screenshots += 1;
+                    }
+
+                    // Toggle recording an image sequence
+                    Event::WindowEvent {
+                        event: WindowEvent::KeyboardInput {
+                            event: KeyEvent {
+                                physical_key: PhysicalKey::Code(KeyCode::F9),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                            ..
+                        },
+                        ..
+                    } => {
+                        if self.is_recording() {
+                            match self.stop_recording() {
+                                Ok(written) => println!("Stopped recording, {} frames written", written),
+                                Err(e) => println!("Failed to finish recording: {:?}", e),
+                            }
+                        } else {
+                            let directory = format!("recording-{}", unix_timestamp());
+                            match self.start_recording(Recording::image_sequence(&directory, ImageFormat::Png)) {
+                                Ok(()) => println!("Recording to {}", directory),
+                                Err(e) => println!("Failed to start recording: {:?}", e),
+                            }
+                        }
+                    }
+
+                    // Camera
+                    Event::WindowEvent {
+                        event: WindowEvent::CursorMoved { position, .. },
+                        ..
+                    } => {
+                        if let (true, Some(last)) = (panning, cursor) {
+                            let delta = Vector::vec_diff(pixel(position), pixel(last));
+                            self.entities.camera.pan(delta);
+                        }
+                        cursor = Some(position);
+                    }
+
+                    Event::WindowEvent {
+                        event: WindowEvent::CursorLeft { .. },
+                        ..
+                    } => {
+                        cursor = None;
+                        panning = false;
+                    }
+
+                    Event::WindowEvent {
+                        event: WindowEvent::MouseInput { button: MouseButton::Right, state, .. },
+                        ..
+                    } => panning = state == ElementState::Pressed,
+
+                    Event::WindowEvent {
+                        event: WindowEvent::MouseWheel { delta, .. },
+                        ..
+                    } => {
+                        let notches = input::scroll_notches(delta);
+                        let size = self.entities.camera.screen_size();
+                        let anchor = cursor.map_or(Vector::vec_scaled(size, 0.5), pixel);
+                        self.entities.camera.zoom_at(SCROLL_ZOOM.powf(notches), anchor);
+                    }
+
+                    Event::WindowEvent {
+                        event: WindowEvent::KeyboardInput {
+                            event: KeyEvent {
+                                physical_key: PhysicalKey::Code(key @ (KeyCode::Tab | KeyCode::KeyC | KeyCode::Home)),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                            ..
+                        },
+                        ..
+                    } => {
+                        let following = match self.entities.camera.mode() {
+                            CameraMode::FollowEntity(index) => Some(index),
+                            _ => None,
+                        };
+                        let next = self.entities.next_followable(following);
+                        let camera = &mut self.entities.camera;
+                        match key {
+                            // Follow the next entity, skipping trails and deleted entities
+                            KeyCode::Tab => if let Some(index) = next {
+                                camera.set_mode(CameraMode::FollowEntity(index));
+                            },
+                            KeyCode::KeyC => camera.set_mode(CameraMode::FollowCenterOfMass),
+                            KeyCode::Home => camera.reset(),
+                            _ => {}
+                        }
+                    }
+
+                    // Simulation speed
+                    Event::WindowEvent {
+                        event: WindowEvent::KeyboardInput {
+                            event: KeyEvent {
+                                physical_key: PhysicalKey::Code(key @ (
+                                    KeyCode::Space | KeyCode::Period | KeyCode::Digit0 |
+                                    KeyCode::Minus | KeyCode::NumpadSubtract | KeyCode::Equal | KeyCode::NumpadAdd
+                                )),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                            ..
+                        },
+                        ..
+                    } => {
+                        match key {
+                            KeyCode::Space if self.is_paused() => self.resume(),
+                            KeyCode::Space => self.pause(),
+                            KeyCode::Period => self.step_once(),
+                            KeyCode::Digit0 => self.set_time_scale(1.0),
+                            KeyCode::Minus | KeyCode::NumpadSubtract => self.set_time_scale(self.time_scale() / TIME_SCALE_STEP),
+                            _ => self.set_time_scale(self.time_scale() * TIME_SCALE_STEP),
+                        }
+                        match key {
+                            KeyCode::Space => println!("{}", if self.is_paused() { "Paused" } else { "Running" }),
+                            KeyCode::Period => {}
+                            _ => println!("Time scale: {}x", self.time_scale()),
+                        }
+                    }
+
                     // Accessing the new_inner_size value?
                     // Event::WindowEvent { event: WindowEvent::ScaleFactorChanged { inner_size_writer, .. }, .. } => {
                     //     let new_size = inner_size_writer.request_inner_size();
@@ -198,35 +526,122 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
             .unwrap();
     }
 
-    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.height > 0 && new_size.width > 0 {
             self.config.height = new_size.height;
             self.config.width = new_size.width;
             self.size.height = new_size.height;
             self.size.width = new_size.width;
-            self.multisample_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("Multisample texture"),
-                size: wgpu::Extent3d { width: self.size.width, height: self.size.height, depth_or_array_layers: 1 },
-                mip_level_count: 1,
-                sample_count: 4,
-                dimension: wgpu::TextureDimension::D2,
-                format: self.config.format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &vec![],
-            });
-            self.surface.configure(&self.device, &self.config);
+            self.multisample_texture = Self::multisample_texture(&self.device, &self.config);
+            self.entities.camera.set_screen_size(new_size.width, new_size.height);
+            match &self.surface {
+                Some(surface) => surface.configure(&self.device, &self.config),
+                None => self.offscreen_texture = Some(Self::offscreen_texture(&self.device, &self.config)),
+            }
+        }
+    }
+
+    /// Renders a frame into the offscreen target and reads it back as tightly packed RGBA8 rows.
+    /// Only available on renderers created with `new_headless`
+    pub fn render_offscreen(&mut self) -> Vec<u8> {
+        assert!(
+            self.offscreen_texture.is_some(),
+            "`render_offscreen` requires a renderer created with `new_headless`"
+        );
+
+        let time = self.timestep.advance_steps(1);
+        self.update(time);
+        self.capture_frame()
+    }
+
+    /// Draws the entities as they currently are, without running the main loop, and reads the
+    /// resolved frame back as tightly packed RGBA8 rows.
+    /// Windowed renderers resolve into a separate texture of the surface's format, since the
+    /// surface itself can't always be copied from
+    pub fn capture_frame(&mut self) -> Vec<u8> {
+        let (width, height) = (self.size.width, self.size.height);
+        let texture = match (self.offscreen_texture.take(), self.capture_texture.take()) {
+            (Some(texture), _) => texture,
+            (None, Some(texture)) if (texture.width(), texture.height()) == (width, height) => texture,
+            _ => Self::offscreen_texture(&self.device, &self.config),
+        };
+
+        self.entities.update_camera();
+        self.draw(&texture.create_view(&TextureViewDescriptor::default()));
+        let pixels = self.read_texture(&texture);
+
+        match self.surface {
+            Some(_) => self.capture_texture = Some(texture),
+            None => self.offscreen_texture = Some(texture),
         }
+        pixels
+    }
+
+    /// Captures the current frame and writes it to `path`, in the format its extension names
+    pub fn save_frame(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let format = ImageFormat::from_path(path).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a .png or .ppm file", path.display())
+        ))?;
+        let pixels = self.capture_frame();
+        image::save_image(path, format, self.size.width, self.size.height, &pixels)
     }
 
-    fn render(&mut self) -> Result<(), SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        
-        let multisample_view = 
+    fn record_frame(&mut self) -> std::io::Result<()> {
+        let pixels = match &self.recorder {
+            Some(recorder) if recorder.wants_frame() => Some(self.capture_frame()),
+            Some(_) => None,
+            None => return Ok(()),
+        };
+
+        let (width, height) = (self.size.width, self.size.height);
+        match &mut self.recorder {
+            Some(recorder) => recorder.record(width, height, pixels.as_deref()),
+            None => Ok(()),
+        }
+    }
+
+    fn render(&mut self, frame_time: Float) -> Result<(), SurfaceError> {
+        let output = match &self.surface {
+            Some(surface) => Some(surface.get_current_texture()?),
+            None => None,
+        };
+
+        let time = if self.recorder.is_some() {
+            self.timestep.advance_steps(1)
+        } else {
+            self.timestep.advance(frame_time)
+        };
+        self.update(time);
+        self.entities.update_camera();
+
+        match output {
+            Some(output) => {
+                self.draw(&output.texture.create_view(&TextureViewDescriptor::default()));
+                output.present();
+            }
+            None => {
+                let texture = self.offscreen_texture.take().unwrap();
+                self.draw(&texture.create_view(&TextureViewDescriptor::default()));
+                self.offscreen_texture = Some(texture);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self, time: FrameTime) {
+        if let Some(func) = &mut self.main_loop {
+            func(&mut self.entities, &self.input, time);
+        }
+        self.input.end_frame();
+    }
+
+    // Draws every entity, resolving into `view`
+    fn draw(&self, view: &TextureView) {
+        let multisample_view =
             self.multisample_texture.create_view(&TextureViewDescriptor::default());
-        
-        let view = output
-            .texture
-            .create_view(&TextureViewDescriptor::default());
 
         let mut encoder = self
             .device
@@ -238,7 +653,7 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
             label: Some("Render pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
                 view: &multisample_view,
-                resolve_target: Some(&view),
+                resolve_target: Some(view),
                 ops: Operations {
                     load: LoadOp::Clear(Color { r: 0.3, g: 0.3, b: 0.3, a: 1.0, }),
                     store: StoreOp::Store,
@@ -248,13 +663,16 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-    
-        match &mut self.main_loop {
-            Some(func) => func(&mut self.entities),
-            None => (),
-        };
 
-        for entity in &self.entities.entities[..] {
+        // Outside of it is left to the clear color, when letterboxing
+        let [x, y, width, height] = self.entities.camera.viewport();
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+
+        // Stable, so entities of the same layer keep their order
+        let mut entities: Vec<&Entity> = self.entities.iter().collect();
+        entities.sort_by_key(|entity| entity.layer);
+
+        for entity in entities {
 
             // Select shader here
             if let Some(render_pipeline) = &entity.render_pipeline {
@@ -267,20 +685,121 @@ impl<'a, T: for<'b> FnMut(&'b mut EntityList)> BaseRenderer<'a, T> {
             render_pass.set_bind_group(0, &entity.transform_bind_group, &[]);
             // Set shader parameters
             render_pass.set_bind_group(1, &entity.shader_bind_group, &[]);
+            // Set the view
+            render_pass.set_bind_group(2, &self.entities.camera.bind_group, &[]);
             // Pass buffers
             render_pass.set_vertex_buffer(0, entity.vertex_buffer.slice(..));
+            if let Some(instance_buffer) = &entity.instance_buffer {
+                render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            }
             render_pass.set_index_buffer(entity.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
             // Draw
-            render_pass.draw_indexed(0..entity.index_size, 0, 0..1);   
+            render_pass.draw_indexed(0..entity.index_size, 0, 0..entity.instance_count);
         }
 
         drop(render_pass);
 
         self.queue.submit(std::iter::once(encoder.finish()));
+    }
 
-        output.present();
+    // Copies a texture of the renderer's size into a buffer and waits for it to be mapped
+    fn read_texture(&mut self, texture: &wgpu::Texture) -> Vec<u8> {
+        let (width, height) = (self.size.width, self.size.height);
+        let unpadded_row = 4 * width;
+        // Rows in a texture-to-buffer copy have to be aligned
+        let padded_row = unpadded_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let size = (padded_row * height) as wgpu::BufferAddress;
+
+        let buffer = match self.readback_buffer.take() {
+            Some(buffer) if buffer.size() == size => buffer,
+            _ => self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Readback buffer"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        };
 
-        Ok(())
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Readback encoder"),
+            });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().expect("Failed to map readback buffer");
+
+        let mut pixels = Vec::with_capacity((unpadded_row * height) as usize);
+        for row in slice.get_mapped_range().chunks(padded_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_row as usize]);
+        }
+        buffer.unmap();
+        self.readback_buffer = Some(buffer);
+
+        // Surfaces commonly prefer BGRA
+        if matches!(texture.format(), wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb) {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        pixels
+    }
+
+    fn multisample_texture(device: &Device, config: &SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisample texture"),
+            size: wgpu::Extent3d { width: config.width, height: config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 4,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    }
+
+    fn offscreen_texture(device: &Device, config: &SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen texture"),
+            size: wgpu::Extent3d { width: config.width, height: config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
     }
 }
 
+fn pixel(position: PhysicalPosition<f64>) -> Vector<Float> {
+    Vector::new(position.x as Float, position.y as Float)
+}
+
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
@@ -14,12 +14,16 @@ struct FragmentOutput {
 @group(0) @binding(0)
 var<uniform> transformation_matrix: mat4x4<f32>;
 
+// World to clip space, shared by every entity
+@group(2) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
 @vertex
 fn vertex(
     vertex_input: VertexInput,
 ) -> VertexOutput {
     var output: VertexOutput;
-    output.position = vec4(vertex_input.position, 1.0, 1.0) * transformation_matrix;
+    output.position = vec4(vertex_input.position, 1.0, 1.0) * transformation_matrix * camera;
     output.color = vec3(0.1, 0.1, 0.1);
     return output;
 }
@@ -0,0 +1,55 @@
+// Converts text mesh files into binary ones, see `renderer::mesh` and `renderer::binary_mesh` for the formats.
+//
+//     mesh_convert <input.mesh> [output.bmesh] [--name <name>]
+//
+// The output defaults to the input with a `.bmesh` extension, and the name to the input's file name without one
+
+use std::path::PathBuf;
+
+use renderer::{
+    binary_mesh::BinaryMesh,
+    mesh::Mesh,
+};
+
+const USAGE: &str = "Usage: mesh_convert <input.mesh> [output.bmesh] [--name <name>]";
+
+fn main() {
+    let mut paths = Vec::new();
+    let mut name = None;
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--name" => name = Some(arguments.next().unwrap_or_else(|| fail(USAGE))),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => paths.push(PathBuf::from(argument)),
+        }
+    }
+
+    let (input, output) = match &paths[..] {
+        [input] => (input.clone(), input.with_extension("bmesh")),
+        [input, output] => (input.clone(), output.clone()),
+        _ => fail(USAGE),
+    };
+    let name = name.unwrap_or_else(|| input.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default());
+
+    let time = std::time::Instant::now();
+    let mesh = Mesh::load(&input).unwrap_or_else(|error| fail(&format!("{}: {}", input.display(), error)));
+    let parsed = time.elapsed();
+
+    let binary = BinaryMesh::from_mesh(name, &mesh)
+        .with_metadata("source", input.file_name().unwrap_or_default().to_string_lossy());
+    binary.save(&output).unwrap_or_else(|error| fail(&format!("{}: {}", output.display(), error)));
+
+    println!(
+        "Wrote {} vertices and {} indices to {} (parsed in {:?})",
+        binary.vertex_count(), binary.index_count(), output.display(), parsed
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,297 @@
+// Binary mesh files are little endian:
+//
+//     magic            b"RNDRMESH"
+//     version          u32
+//     name             u32 length, UTF-8
+//     metadata         u32 count, per entry a key and a value stored like the name
+//     vertex format    u32 attribute count, per attribute kind u8 (0 position, 1 color, 2 UV, 3 custom)
+//                      and, for custom ones, the index of its format in `VERTEX_FORMATS`, 0 otherwise
+//     vertex count     u32
+//     index count      u32
+//     padding          zeroes up to a multiple of 4 bytes
+//     vertices         vertex count times the format's stride, interleaved as in `VertexData::bytes`
+//     indices          index count u32
+//
+// Loading reads the file once and hands slices of it to the GPU. Past the header only the indices
+// are decoded, to check that each is in range for the vertex count
+
+use std::{
+    fmt,
+    io::{ self, Read, Write },
+    ops::Range,
+    path::Path
+};
+
+use crate::{
+    mesh::Mesh,
+    utils::{
+        as_u8_slice,
+        defaults::Index
+    },
+    vertex::{ Attribute, VertexData, VertexFormat }
+};
+
+const MAGIC: &[u8; 8] = b"RNDRMESH";
+pub const VERSION: u32 = 1;
+
+// Custom attribute formats by their number in the file, new ones may only be added to the end
+const VERTEX_FORMATS: [wgpu::VertexFormat; 34] = {
+    use wgpu::VertexFormat::*;
+    [
+        Uint8x2, Uint8x4, Sint8x2, Sint8x4, Unorm8x2, Unorm8x4, Snorm8x2, Snorm8x4,
+        Uint16x2, Uint16x4, Sint16x2, Sint16x4, Unorm16x2, Unorm16x4, Snorm16x2, Snorm16x4,
+        Float16x2, Float16x4, Float32, Float32x2, Float32x3, Float32x4,
+        Uint32, Uint32x2, Uint32x3, Uint32x4, Sint32, Sint32x2, Sint32x3, Sint32x4,
+        Float64, Float64x2, Float64x3, Float64x4,
+    ]
+};
+
+#[derive(Debug)]
+pub enum BinaryMeshError {
+    Io(io::Error),
+    NotAMesh,
+    UnsupportedVersion(u32),
+    Corrupt(String),
+}
+
+impl fmt::Display for BinaryMeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryMeshError::Io(error) => write!(f, "Failed to read mesh: {}", error),
+            BinaryMeshError::NotAMesh => write!(f, "Not a binary mesh file"),
+            BinaryMeshError::UnsupportedVersion(version) => write!(f, "Binary mesh version {} is not supported, {} is", version, VERSION),
+            BinaryMeshError::Corrupt(message) => write!(f, "Corrupt binary mesh: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for BinaryMeshError {}
+
+impl From<io::Error> for BinaryMeshError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => BinaryMeshError::Corrupt("File ends early".to_string()),
+            _ => BinaryMeshError::Io(error),
+        }
+    }
+}
+
+/// A mesh as stored in a binary mesh file, its vertices and indices kept as the bytes sent to the GPU
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryMesh {
+    name: String,
+    metadata: Vec<(String, String)>,
+    format: VertexFormat,
+    vertex_count: usize,
+    index_count: usize,
+    // The whole file when loaded, just the vertices and indices when built
+    data: Vec<u8>,
+    vertices: Range<usize>,
+    indices: Range<usize>,
+}
+
+impl BinaryMesh {
+    pub fn new(name: impl Into<String>, vertices: &VertexData, indices: &[Index]) -> Self {
+        let mut data = vertices.bytes();
+        let vertex_end = data.len();
+        data.extend_from_slice(as_u8_slice(indices));
+
+        Self {
+            name: name.into(),
+            metadata: Vec::new(),
+            format: vertices.format(),
+            vertex_count: vertices.len(),
+            index_count: indices.len(),
+            vertices: 0..vertex_end,
+            indices: vertex_end..data.len(),
+            data,
+        }
+    }
+
+    pub fn from_mesh(name: impl Into<String>, mesh: &Mesh) -> Self {
+        Self::new(name, &mesh.vertex_data(), &mesh.indices)
+    }
+
+    /// Adds a key and value to store alongside the mesh, e.g. where it came from
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+
+    pub fn metadata(&self) -> &[(String, String)] { &self.metadata }
+
+    /// The value of the first entry with `key`
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, value)| value.as_str())
+    }
+
+    pub fn format(&self) -> &VertexFormat { &self.format }
+
+    pub fn vertex_count(&self) -> usize { self.vertex_count }
+
+    pub fn index_count(&self) -> usize { self.index_count }
+
+    /// Interleaved as `format`
+    pub fn vertex_bytes(&self) -> &[u8] { &self.data[self.vertices.clone()] }
+
+    /// Little endian u32s
+    pub fn index_bytes(&self) -> &[u8] { &self.data[self.indices.clone()] }
+
+    /// A copy of the indices, the bytes aren't necessarily aligned for reading them in place
+    pub fn indices(&self) -> Vec<Index> {
+        self.index_bytes().chunks_exact(4).map(|bytes| Index::from_le_bytes(bytes.try_into().unwrap())).collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = std::fs::File::create(path)?;
+        self.write(&mut file)?;
+        file.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BinaryMeshError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut header = Vec::new();
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        write_string(&mut header, &self.name)?;
+        header.extend_from_slice(&count(self.metadata.len(), "metadata entries")?);
+        for (key, value) in &self.metadata {
+            write_string(&mut header, key)?;
+            write_string(&mut header, value)?;
+        }
+
+        header.extend_from_slice(&count(self.format.attributes().len(), "attributes")?);
+        for attribute in self.format.attributes() {
+            let (kind, format) = match attribute {
+                Attribute::Position => (0, 0),
+                Attribute::Color => (1, 0),
+                Attribute::Uv => (2, 0),
+                Attribute::Custom(format) => {
+                    let number = VERTEX_FORMATS.iter().position(|known| known == format)
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} can't be stored", format)))?;
+                    (3, number as u8)
+                }
+            };
+            header.extend_from_slice(&[kind, format]);
+        }
+
+        header.extend_from_slice(&count(self.vertex_count, "vertices")?);
+        header.extend_from_slice(&count(self.index_count, "indices")?);
+        header.resize(header.len().next_multiple_of(4), 0);
+
+        writer.write_all(&header)?;
+        writer.write_all(self.vertex_bytes())?;
+        writer.write_all(self.index_bytes())
+    }
+
+    /// Takes over `data`, the vertices and indices stay where they are in it
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, BinaryMeshError> {
+        let mut reader = &data[..];
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic).map_err(|_| BinaryMeshError::NotAMesh)?;
+        if &magic != MAGIC {
+            return Err(BinaryMeshError::NotAMesh);
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(BinaryMeshError::UnsupportedVersion(version));
+        }
+
+        let name = read_string(&mut reader)?;
+        let entries = read_u32(&mut reader)?;
+        let mut metadata = Vec::new();
+        for _ in 0..entries {
+            metadata.push((read_string(&mut reader)?, read_string(&mut reader)?));
+        }
+
+        let attribute_count = read_u32(&mut reader)?;
+        let mut attributes = Vec::new();
+        for _ in 0..attribute_count {
+            let [kind, format] = read_bytes(&mut reader)?;
+            attributes.push(match kind {
+                0 => Attribute::Position,
+                1 => Attribute::Color,
+                2 => Attribute::Uv,
+                3 => Attribute::Custom(*VERTEX_FORMATS.get(format as usize)
+                    .ok_or_else(|| BinaryMeshError::Corrupt(format!("Unknown vertex format {}", format)))?),
+                _ => return Err(BinaryMeshError::Corrupt(format!("Unknown attribute kind {}", kind))),
+            });
+        }
+        // `VertexFormat::new` would panic on these
+        if attributes.first() != Some(&Attribute::Position)
+            || (1..attributes.len()).any(|i| !matches!(attributes[i], Attribute::Custom(_)) && attributes[..i].contains(&attributes[i])) {
+            return Err(BinaryMeshError::Corrupt(format!("Invalid vertex format {:?}", attributes)));
+        }
+        let format = VertexFormat::new(&attributes);
+
+        let vertex_count = read_u32(&mut reader)? as usize;
+        let index_count = read_u32(&mut reader)? as usize;
+
+        let header_end = (data.len() - reader.len()).next_multiple_of(4);
+        let vertex_end = header_end as u64 + vertex_count as u64 * format.stride();
+        let index_end = vertex_end + index_count as u64 * 4;
+        if index_end != data.len() as u64 {
+            return Err(BinaryMeshError::Corrupt(format!(
+                "Expected {} bytes for {} vertices and {} indices, the file has {}", index_end, vertex_count, index_count, data.len()
+            )));
+        }
+
+        let mesh = Self {
+            name,
+            metadata,
+            format,
+            vertex_count,
+            index_count,
+            vertices: header_end..vertex_end as usize,
+            indices: vertex_end as usize..index_end as usize,
+            data,
+        };
+
+        // Checked here rather than by the GPU
+        if let Some(index) = mesh.indices().into_iter().find(|&index| index as usize >= vertex_count) {
+            return Err(BinaryMeshError::Corrupt(format!("Index {} is out of range for {} vertices", index, vertex_count)));
+        }
+
+        Ok(mesh)
+    }
+}
+
+// Counts and lengths are stored as u32
+fn count(count: usize, what: &str) -> io::Result<[u8; 4]> {
+    u32::try_from(count)
+        .map(u32::to_le_bytes)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{} {} don't fit in a binary mesh", count, what)))
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) -> io::Result<()> {
+    bytes.extend_from_slice(&count(string.len(), "bytes of text")?);
+    bytes.extend_from_slice(string.as_bytes());
+    Ok(())
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_string(reader: &mut &[u8]) -> Result<String, BinaryMeshError> {
+    let length = read_u32(reader)? as usize;
+    if length > reader.len() {
+        return Err(BinaryMeshError::Corrupt("File ends early".to_string()));
+    }
+    let (string, rest) = reader.split_at(length);
+    *reader = rest;
+    String::from_utf8(string.to_vec()).map_err(|_| BinaryMeshError::Corrupt("Text is not UTF-8".to_string()))
+}
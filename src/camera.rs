@@ -0,0 +1,227 @@
+use wgpu::util::DeviceExt;
+
+use crate::{
+    entity::Entity,
+    utils::{
+        self, as_u8_slice,
+        defaults::Float,
+        Mat4x4, Vector
+    }
+};
+
+/// What the camera keeps centered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    // Stays where it was put
+    Free,
+    // The entity at this index
+    FollowEntity(usize),
+    // Whatever was last passed to `Camera::set_center_of_mass`, the camera knows nothing of masses
+    FollowCenterOfMass,
+}
+
+/// How the world bounds are fitted into a window of a different shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AspectMode {
+    // All of the bounds are visible, with more of the world shown along the longer side of the window
+    Fit,
+    // The bounds cover the whole window, cut off along its longer side
+    Fill,
+    // All of the bounds and nothing else, with bars of the background along the longer side of the window
+    Letterbox,
+    // The bounds are squashed to the window, circles turn into ellipses
+    Stretch,
+}
+
+/// A 2D camera with an orthographic projection, its view-projection matrix is bound to group 2 of every pipeline.
+/// World units are independent of pixels, at a zoom of 1 the bounds around the center are visible
+pub struct Camera {
+    center: Vector<Float>,
+    zoom: Float,
+    mode: CameraMode,
+    center_of_mass: Vector<Float>,
+    // Half the width and height of the world visible at a zoom of 1
+    bounds: Vector<Float>,
+    aspect_mode: AspectMode,
+    // In pixels
+    screen_size: Vector<Float>,
+    buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+}
+
+impl Camera {
+    pub const MIN_ZOOM: Float = 1e-4;
+    pub const MAX_ZOOM: Float = 1e4;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: as_u8_slice(&[Mat4x4::identity()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &utils::generate_camera_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }]
+        });
+
+        Self {
+            center: Vector::new(0.0, 0.0),
+            zoom: 1.0,
+            mode: CameraMode::Free,
+            center_of_mass: Vector::new(0.0, 0.0),
+            bounds: Vector::new(1.0, 1.0),
+            aspect_mode: AspectMode::Fit,
+            screen_size: Vector::new(1.0, 1.0),
+            buffer,
+            bind_group,
+        }
+    }
+
+    pub fn center(&self) -> Vector<Float> { self.center }
+
+    /// Moves the camera, stopping it from following anything
+    pub fn set_center(&mut self, center: Vector<Float>) {
+        self.center = center;
+        self.mode = CameraMode::Free;
+    }
+
+    pub fn zoom(&self) -> Float { self.zoom }
+
+    pub fn set_zoom(&mut self, zoom: Float) {
+        self.zoom = zoom.clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    pub fn mode(&self) -> CameraMode { self.mode }
+
+    pub fn set_mode(&mut self, mode: CameraMode) { self.mode = mode; }
+
+    pub fn set_center_of_mass(&mut self, center_of_mass: Vector<Float>) {
+        self.center_of_mass = center_of_mass;
+    }
+
+    pub fn bounds(&self) -> Vector<Float> { self.bounds }
+
+    /// Sets the half width and half height of the world visible at a zoom of 1
+    pub fn set_bounds(&mut self, half_extents: Vector<Float>) {
+        assert!(half_extents.x() > 0.0 && half_extents.y() > 0.0, "Camera bounds have to be positive");
+        self.bounds = half_extents;
+    }
+
+    pub fn aspect_mode(&self) -> AspectMode { self.aspect_mode }
+
+    pub fn set_aspect_mode(&mut self, aspect_mode: AspectMode) { self.aspect_mode = aspect_mode; }
+
+    pub fn screen_size(&self) -> Vector<Float> { self.screen_size }
+
+    /// Kept up to date by the renderer on every resize
+    pub fn set_screen_size(&mut self, width: u32, height: u32) {
+        self.screen_size = Vector::new(width.max(1) as Float, height.max(1) as Float);
+    }
+
+    /// Back to the origin at a zoom of 1, following nothing
+    pub fn reset(&mut self) {
+        self.center = Vector::new(0.0, 0.0);
+        self.zoom = 1.0;
+        self.mode = CameraMode::Free;
+    }
+
+    /// The part of the window drawn to as x, y, width and height in pixels. All of it unless letterboxing
+    pub fn viewport(&self) -> [Float; 4] {
+        let (width, height) = (self.screen_size.x(), self.screen_size.y());
+        if self.aspect_mode != AspectMode::Letterbox {
+            return [0.0, 0.0, width, height];
+        }
+
+        let bounds_aspect = self.bounds.x() / self.bounds.y();
+        if width / height > bounds_aspect {
+            let viewport_width = height * bounds_aspect;
+            [(width - viewport_width) * 0.5, 0.0, viewport_width, height]
+        } else {
+            let viewport_height = width / bounds_aspect;
+            [0.0, (height - viewport_height) * 0.5, width, viewport_height]
+        }
+    }
+
+    // Clip space units per world unit on each axis
+    fn scale(&self) -> Vector<Float> {
+        let [_, _, width, height] = self.viewport();
+        let screen_aspect = width / height;
+        let bounds_aspect = self.bounds.x() / self.bounds.y();
+
+        // How much of the world beyond the bounds is visible along each axis
+        let (extra_x, extra_y) = match self.aspect_mode {
+            AspectMode::Stretch | AspectMode::Letterbox => (1.0, 1.0),
+            AspectMode::Fit if screen_aspect > bounds_aspect => (screen_aspect / bounds_aspect, 1.0),
+            AspectMode::Fit => (1.0, bounds_aspect / screen_aspect),
+            AspectMode::Fill if screen_aspect > bounds_aspect => (1.0, bounds_aspect / screen_aspect),
+            AspectMode::Fill => (screen_aspect / bounds_aspect, 1.0),
+        };
+
+        Vector::new(self.zoom / (self.bounds.x() * extra_x), self.zoom / (self.bounds.y() * extra_y))
+    }
+
+    /// World position under a pixel of the window, measured from its top left corner
+    pub fn screen_to_world(&self, pixel: Vector<Float>) -> Vector<Float> {
+        let [x, y, width, height] = self.viewport();
+        let clip = Vector::new((pixel.x() - x) / width * 2.0 - 1.0, 1.0 - (pixel.y() - y) / height * 2.0);
+        let scale = self.scale();
+        Vector::new(self.center.x() + clip.x() / scale.x(), self.center.y() + clip.y() / scale.y())
+    }
+
+    /// Pixel of the window a point in the world is drawn at
+    pub fn world_to_screen(&self, world: Vector<Float>) -> Vector<Float> {
+        let [x, y, width, height] = self.viewport();
+        let scale = self.scale();
+        let clip = Vector::new((world.x() - self.center.x()) * scale.x(), (world.y() - self.center.y()) * scale.y());
+        Vector::new(x + (clip.x() + 1.0) * 0.5 * width, y + (1.0 - clip.y()) * 0.5 * height)
+    }
+
+    /// Drags the view along by `pixel_delta`, so whatever was under the cursor stays under it
+    pub fn pan(&mut self, pixel_delta: Vector<Float>) {
+        let scale = self.scale();
+        let [_, _, width, height] = self.viewport();
+        let world_delta = Vector::new(pixel_delta.x() * 2.0 / width / scale.x(), -pixel_delta.y() * 2.0 / height / scale.y());
+        self.set_center(Vector::vec_diff(self.center, world_delta));
+    }
+
+    /// Zooms by `factor` around a pixel of the window, which stays put on screen.
+    /// When following something the camera stays centered on it instead
+    pub fn zoom_at(&mut self, factor: Float, pixel: Vector<Float>) {
+        let anchor = self.screen_to_world(pixel);
+        self.set_zoom(self.zoom * factor);
+        if self.mode == CameraMode::Free {
+            let moved = self.screen_to_world(pixel);
+            self.center.add_vec(Vector::vec_diff(anchor, moved));
+        }
+    }
+
+    // Maps world space to clip space, applied after every entity's transform
+    fn matrix(&self) -> Mat4x4 {
+        let scale = self.scale();
+        Mat4x4::new(
+            scale.x(), 0.0, -self.center.x() * scale.x(), 0.0,
+            0.0, scale.y(), -self.center.y() * scale.y(), 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
+
+    /// Catches up with whatever is followed and sends the matrix to the GPU
+    pub(crate) fn update(&mut self, entities: &[Option<Entity>], queue: &wgpu::Queue) {
+        match self.mode {
+            CameraMode::Free => {}
+            CameraMode::FollowEntity(index) => match entities.get(index).and_then(Option::as_ref) {
+                Some(entity) => self.center = entity.position(),
+                None => self.mode = CameraMode::Free,
+            },
+            CameraMode::FollowCenterOfMass => self.center = self.center_of_mass,
+        }
+
+        queue.write_buffer(&self.buffer, 0, as_u8_slice(&[self.matrix()]));
+    }
+}
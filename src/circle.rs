@@ -5,33 +5,64 @@ use crate::utils::{
 };
 use crate::utils;
 use crate::entity::EntityList;
+use crate::integrator::Integrator;
+use crate::gravity::GravitySolver;
+use crate::collision::{ self, CollisionResponse };
+use crate::trail::{ Trail, TrailStyle };
 
-// required for wgpu to not scream at you
-#[repr(align(16))]
-struct CircleColor {
-    color: [f32; 3],
+/// How a circle is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CircleShader {
+    // The renderer's default, dark grey
+    Base,
+    Color([f32; 3]),
+}
+
+/// Everything needed to recreate a circle, see `Circle::state` and `Circle::from_state`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircleState {
+    pub mass: usize,
+    pub radius: f32,
+    pub position: Vector<Float>,
+    pub previous_position: Vector<Float>,
+    pub velocity: Vector<Float>,
+    pub acceleration: Vector<Float>,
+    pub shader: CircleShader,
 }
 
 pub struct Circle {
     mass: usize,
     radius: f32,
+    shader: CircleShader,
     index: usize,
     position: Vector<Float>,
+    // Position before the last step, for interpolating between steps
+    previous_position: Vector<Float>,
     velocity: Vector<Float>,
     acceleration: Vector<Float>,
+    // Where it has been, if asked to keep track
+    trail: Option<Trail>,
 }
 
 impl Circle {
     pub fn new(entity_list: &mut EntityList, mass: usize, radius: f32, position: Vector<Float>, velocity: Vector<Float>, acceleration: Vector<Float>) -> Circle {
-        let index = entity_list.count();
+        let index = entity_list.next_index();
+        let shader = match index {
+            1 => CircleShader::Base,
+            2 => CircleShader::Color([0.6, 0.4, 0.1]),
+            _ => CircleShader::Color([0.0, 0.5, 0.5]),
+        };
+
+        let mut circle = Self::with_shader(entity_list, mass, radius, position, velocity, shader);
+        circle.acceleration = acceleration;
+        circle
+    }
+
+    pub fn with_shader(entity_list: &mut EntityList, mass: usize, radius: f32, position: Vector<Float>, velocity: Vector<Float>, shader: CircleShader) -> Circle {
+        let index = entity_list.next_index();
         let circle = entity_list.add_entity();
         
-        let verts = utils::generate_regular_geometry(20, radius, Vector::new(0.0, 0.0), 0.0);
-        let mut indices = Vec::with_capacity(verts.len());
-        for i in 0..verts.len() {
-            indices.push(i as u32);
-        }
-        let tris_right = utils::generate_triangles(indices);
+        let (verts, tris_right) = Self::geometry(radius);
         circle.set_geometry(&verts, &tris_right);
 
         let mut circle_transform = Mat4x4::identity();
@@ -39,76 +70,219 @@ impl Circle {
 
         circle.set_transform(circle_transform);
 
-        if index != 1 {
+        if let CircleShader::Color(color) = shader {
             circle.set_shader(wgpu::include_wgsl!("color_shader.wgsl"));
 
-            let circle_color = CircleColor { color: [0.0, 0.5, 0.5] };
-    
-            circle.set_shader_args(circle_color);
-        }
-
-        // Test send_shader_args fn
-        if index == 2 {
-            let circle_color = CircleColor { color: [0.6, 0.4, 0.1] };
-            circle.send_shader_args(circle_color);
+            // A vec3 uniform takes up 16 bytes, the last float is padding
+            let [r, g, b] = color;
+            circle.set_shader_args([r, g, b, 0.0]);
         }
 
         Circle {
             mass,
             radius,
+            shader,
             position,
+            previous_position: position,
             velocity,
-            acceleration,
-            index
+            acceleration: Vector::new(0.0, 0.0),
+            index,
+            trail: None,
         }
     }
 
-    // Update gravity calculations for each circle within a provided vec containing circles
-    pub fn gravity(circles: &mut Vec<Circle>) {
-        let circle_len = circles.len();
-        for i in 0..circle_len {
-            let circle_pos = circles[i].position;
-            for j in 0..circle_len {
-                if i == j { continue; }
-
-                const G: f32 = 0.0000001;
-
-                let other_circle_pos = circles[j].position;
-                let other_circle_mass = circles[j].mass as f32;
-                let pos_diff = 
-                    Vector::new(
-                        circle_pos.x() - other_circle_pos.x(), 
-                        circle_pos.y() - other_circle_pos.y()
-                    );
-
-                let pos_diff_mag_sq = pos_diff.x() * pos_diff.x() + pos_diff.y() * pos_diff.y();
-                let force_mag = G * other_circle_mass / pos_diff_mag_sq;
-                
-                let force = Vector::new(
-                    -(pos_diff.x() / pos_diff_mag_sq.sqrt()) * force_mag, 
-                    -(pos_diff.y() / pos_diff_mag_sq.sqrt()) * force_mag
-                );
-
-                let acceleration_circle = force;
-
-                unsafe {
-                    circles.get_unchecked_mut(i).acceleration.add_vec(acceleration_circle);
-                }
+    pub fn state(&self) -> CircleState {
+        CircleState {
+            mass: self.mass,
+            radius: self.radius,
+            position: self.position,
+            previous_position: self.previous_position,
+            velocity: self.velocity,
+            acceleration: self.acceleration,
+            shader: self.shader,
+        }
+    }
+
+    /// Adds a circle picking up exactly where `state` left off
+    pub fn from_state(entity_list: &mut EntityList, state: &CircleState) -> Circle {
+        let mut circle = Self::with_shader(entity_list, state.mass, state.radius, state.position, state.velocity, state.shader);
+        circle.previous_position = state.previous_position;
+        circle.acceleration = state.acceleration;
+        circle
+    }
+
+    fn geometry(radius: f32) -> (Vec<Vector<Float>>, Vec<u32>) {
+        let verts = utils::generate_regular_geometry(20, radius, Vector::new(0.0, 0.0), 0.0);
+        let mut indices = Vec::with_capacity(verts.len());
+        for i in 0..verts.len() {
+            indices.push(i as u32);
+        }
+        let tris = utils::generate_triangles(indices);
+        (verts, tris)
+    }
+
+    /// Starts drawing a trail behind the circle, replacing any it had. Its entity is added to `entity_list`
+    pub fn set_trail(&mut self, entity_list: &mut EntityList, style: TrailStyle) {
+        if let Some(trail) = self.trail.take() {
+            trail.remove(entity_list);
+        }
+        self.trail = Some(Trail::new(entity_list, style));
+    }
+
+    pub fn trail(&self) -> Option<&Trail> { self.trail.as_ref() }
+
+    pub fn trail_mut(&mut self) -> Option<&mut Trail> { self.trail.as_mut() }
+
+    /// Index of the entity drawing the circle
+    pub fn entity_index(&self) -> usize { self.index }
+
+    pub fn radius(&self) -> f32 { self.radius }
+
+    pub fn mass(&self) -> usize { self.mass }
+
+    pub fn position(&self) -> Vector<Float> { self.position }
+
+    pub fn velocity(&self) -> Vector<Float> { self.velocity }
+
+    pub fn set_velocity(&mut self, velocity: Vector<Float>) { self.velocity = velocity; }
+
+    /// Moves the circle straight to `position`, without interpolating from where it was
+    pub fn place(&mut self, entity_list: &mut EntityList, position: Vector<Float>) {
+        self.position = position;
+        self.previous_position = position;
+        entity_list.get_entity_unchecked(self.index).translate_to(position);
+    }
+
+    // Index of the circle within a given vec that covers `point`, the one whose center is closest if they overlap
+    pub fn pick(circles: &[Circle], point: Vector<Float>) -> Option<usize> {
+        circles
+            .iter()
+            .enumerate()
+            .map(|(i, circle)| (i, Vector::vec_diff(circle.position, point).mag(), circle.radius))
+            .filter(|&(_, distance, radius)| distance <= radius * radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _, _)| i)
+    }
+
+    // Update gravity calculations for each circle within a provided vec containing circles, as computed by `solver`
+    pub fn gravity(circles: &mut [Circle], solver: &mut dyn GravitySolver) {
+        let positions: Vec<Vector<Float>> = circles.iter().map(|circle| circle.position).collect();
+        let masses: Vec<Float> = circles.iter().map(|circle| circle.mass as Float).collect();
+        let mut accelerations = vec![Vector::new(0.0, 0.0); circles.len()];
+
+        solver.accelerations(&positions, &masses, &mut accelerations);
+
+        for (circle, acceleration) in circles.iter_mut().zip(accelerations) {
+            circle.acceleration.add_vec(acceleration);
+        }
+    }
+
+    // Advance each circle within a given vec of circles by `dt` seconds under their mutual gravity, as computed by `solver`,
+    // using `integrator`. Accelerations added through `gravity` are not used
+    pub fn step(
+        circles: &mut [Circle], 
+        entity_list: &mut EntityList, 
+        integrator: &mut dyn Integrator, 
+        solver: &mut dyn GravitySolver, 
+        dt: Float
+    ) {
+        let masses: Vec<Float> = circles.iter().map(|circle| circle.mass as Float).collect();
+        let mut positions: Vec<Vector<Float>> = circles.iter().map(|circle| circle.position).collect();
+        let mut velocities: Vec<Vector<Float>> = circles.iter().map(|circle| circle.velocity).collect();
+
+        integrator.step(&mut positions, &mut velocities, dt, &mut |positions, accelerations| {
+            solver.accelerations(positions, &masses, accelerations)
+        });
+
+        for ((circle, position), velocity) in circles.iter_mut().zip(positions).zip(velocities) {
+            circle.previous_position = circle.position;
+            circle.position = position;
+            circle.velocity = velocity;
+            entity_list.get_entity_unchecked(circle.index).translate_to(position);
+            // Positions only make it into the trail once the circle is drawn past them
+            if let Some(trail) = &mut circle.trail {
+                trail.push(circle.previous_position);
             }
         }
     }
 
-    // Update circle data in each circle element within a given vec of circles
-    pub fn update(circles: &mut Vec<Circle>, entity_list: &mut EntityList) {
+    // Advance each circle within a given vec of circles by `dt` seconds
+    pub fn update(circles: &mut [Circle], entity_list: &mut EntityList, dt: Float) {
         for circle in circles {
             let entity = entity_list.get_entity_unchecked(circle.index);
             
-            circle.velocity.add_vec(circle.acceleration);
+            circle.velocity.add_vec(Vector::vec_scaled(circle.acceleration, dt));
 
-            circle.position.add_vec(circle.velocity);
-            entity.translate_by(circle.velocity);
+            circle.previous_position = circle.position;
+            circle.position.add_vec(Vector::vec_scaled(circle.velocity, dt));
+            entity.translate_to(circle.position);
+            if let Some(trail) = &mut circle.trail {
+                trail.push(circle.previous_position);
+            }
 
             circle.acceleration = Vector::new(0.0, 0.0);
         }
     }
+
+    // Resolve collisions between the circles within a given vec. Circles absorbed by merging are removed from the vec,
+    // and their entities deleted from `entity_list`
+    pub fn collide(circles: &mut Vec<Circle>, entity_list: &mut EntityList, response: CollisionResponse) {
+        if response == CollisionResponse::Ignore { return; }
+
+        let mut positions: Vec<Vector<Float>> = circles.iter().map(|circle| circle.position).collect();
+        let mut velocities: Vec<Vector<Float>> = circles.iter().map(|circle| circle.velocity).collect();
+        let mut masses: Vec<Float> = circles.iter().map(|circle| circle.mass as Float).collect();
+        let mut radii: Vec<Float> = circles.iter().map(|circle| circle.radius).collect();
+
+        let absorbed = collision::resolve(response, &mut positions, &mut velocities, &mut masses, &mut radii);
+
+        for (i, circle) in circles.iter_mut().enumerate() {
+            let entity = entity_list.get_entity_unchecked(circle.index);
+            if circle.radius != radii[i] {
+                circle.radius = radii[i];
+                let (verts, tris) = Self::geometry(circle.radius);
+                entity.set_geometry(&verts, &tris);
+            }
+            if circle.position.pos != positions[i].pos {
+                // Jumps straight there, interpolating would draw it inside the other one
+                circle.position = positions[i];
+                circle.previous_position = positions[i];
+                entity.translate_to(positions[i]);
+            }
+            circle.velocity = velocities[i];
+            circle.mass = masses[i] as usize;
+        }
+
+        for &index in absorbed.iter().rev() {
+            let circle = circles.remove(index);
+            entity_list.delete_entity(circle.index);
+            if let Some(trail) = circle.trail {
+                trail.remove(entity_list);
+            }
+        }
+    }
+
+    // Mass weighted average position of the circles within a given vec
+    pub fn center_of_mass(circles: &[Circle]) -> Vector<Float> {
+        let mut weighted = Vector::new(0.0, 0.0);
+        let mut mass = 0.0;
+        for circle in circles {
+            weighted.add_vec(Vector::vec_scaled(circle.position, circle.mass as Float));
+            mass += circle.mass as Float;
+        }
+        if mass > 0.0 { Vector::vec_scaled(weighted, 1.0 / mass) } else { weighted }
+    }
+
+    // Draw each circle `alpha` of the way from its previous position to its current one, with its trail leading up to it
+    pub fn interpolate(circles: &mut [Circle], entity_list: &mut EntityList, alpha: Float) {
+        for circle in circles {
+            let entity = entity_list.get_entity_unchecked(circle.index);
+            let travelled = Vector::vec_diff(circle.position, circle.previous_position);
+            let drawn = Vector::vec_sum(circle.previous_position, Vector::vec_scaled(travelled, alpha));
+            entity.translate_to(drawn);
+            if let Some(trail) = &mut circle.trail {
+                trail.upload(entity_list, Some(drawn));
+            }
+        }
+    }
 }
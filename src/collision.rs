@@ -0,0 +1,151 @@
+use crate::utils::{
+    defaults::Float,
+    Vector
+};
+
+/// What happens to two bodies that touch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionResponse {
+    /// Bounce off each other. A restitution of 1 keeps all the kinetic energy, 0 none along the contact normal
+    Elastic { restitution: Float },
+    /// Stick together into one body, conserving mass, momentum and area
+    Merge,
+    /// Pass through each other
+    Ignore,
+}
+
+/// Two overlapping bodies, `a < b`
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    pub a: usize,
+    pub b: usize,
+    // Unit vector from `a` towards `b`
+    pub normal: Vector<Float>,
+    // How far they overlap
+    pub depth: Float,
+}
+
+/// Every pair of overlapping circles. Sweep and prune along x, so only bodies whose
+/// horizontal extents overlap are tested against each other
+pub fn contacts(positions: &[Vector<Float>], radii: &[Float]) -> Vec<Contact> {
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_unstable_by(|&i, &j| (positions[i].x() - radii[i]).total_cmp(&(positions[j].x() - radii[j])));
+
+    let mut contacts = Vec::new();
+    for (rank, &i) in order.iter().enumerate() {
+        let right = positions[i].x() + radii[i];
+        for &j in &order[rank + 1..] {
+            if positions[j].x() - radii[j] > right { break; }
+
+            let diff = Vector::vec_diff(positions[j], positions[i]);
+            let distance_sq = diff.mag();
+            let reach = radii[i] + radii[j];
+            if distance_sq >= reach * reach { continue; }
+
+            let distance = distance_sq.sqrt();
+            // Bodies on top of each other get pushed apart sideways
+            let normal = if distance > 0.0 { Vector::vec_scaled(diff, 1.0 / distance) } else { Vector::new(1.0, 0.0) };
+            let (a, b, normal) = if i < j { (i, j, normal) } else { (j, i, Vector::vec_scaled(normal, -1.0)) };
+            contacts.push(Contact { a, b, normal, depth: reach - distance });
+        }
+    }
+
+    contacts.sort_unstable_by_key(|contact| (contact.a, contact.b));
+    contacts
+}
+
+/// Bounces every contact with the given restitution, and pushes the bodies apart so they no longer overlap.
+/// Bodies without a positive, finite mass are immovable, a pair of them is left as is
+pub fn bounce(
+    contacts: &[Contact],
+    positions: &mut [Vector<Float>],
+    velocities: &mut [Vector<Float>],
+    masses: &[Float],
+    restitution: Float,
+) {
+    for contact in contacts {
+        let (a, b) = (contact.a, contact.b);
+        let inverse_a = inverse_mass(masses[a]);
+        let inverse_b = inverse_mass(masses[b]);
+        let inverse_sum = inverse_a + inverse_b;
+        // Neither can move, or a mass so small its inverse overflows
+        if inverse_sum == 0.0 || !inverse_sum.is_finite() { continue; }
+
+        // Move apart in proportion to the inverse masses, heavy bodies barely budge
+        let correction = Vector::vec_scaled(contact.normal, contact.depth / inverse_sum);
+        positions[a].sub_vec(Vector::vec_scaled(correction, inverse_a));
+        positions[b].add_vec(Vector::vec_scaled(correction, inverse_b));
+
+        let relative = Vector::vec_diff(velocities[b], velocities[a]);
+        let approach = relative.x() * contact.normal.x() + relative.y() * contact.normal.y();
+        // Already moving apart
+        if approach >= 0.0 { continue; }
+
+        let impulse = Vector::vec_scaled(contact.normal, -(1.0 + restitution) * approach / inverse_sum);
+        velocities[a].sub_vec(Vector::vec_scaled(impulse, inverse_a));
+        velocities[b].add_vec(Vector::vec_scaled(impulse, inverse_b));
+    }
+}
+
+fn inverse_mass(mass: Float) -> Float {
+    if mass > 0.0 && mass.is_finite() { 1.0 / mass } else { 0.0 }
+}
+
+/// Merges every contact into its heavier body, which moves to their center of mass.
+/// Returns the absorbed bodies in ascending order, their entries are left untouched.
+/// A body touching several others only absorbs or gets absorbed once per call
+pub fn merge(
+    contacts: &[Contact],
+    positions: &mut [Vector<Float>],
+    velocities: &mut [Vector<Float>],
+    masses: &mut [Float],
+    radii: &mut [Float],
+) -> Vec<usize> {
+    let mut touched = vec![false; positions.len()];
+    let mut absorbed = Vec::new();
+
+    for contact in contacts {
+        if touched[contact.a] || touched[contact.b] { continue; }
+        touched[contact.a] = true;
+        touched[contact.b] = true;
+
+        let (survivor, other) = if masses[contact.b] > masses[contact.a] { (contact.b, contact.a) } else { (contact.a, contact.b) };
+        let mass = masses[survivor] + masses[other];
+
+        let weighted_position = Vector::vec_sum(
+            Vector::vec_scaled(positions[survivor], masses[survivor]),
+            Vector::vec_scaled(positions[other], masses[other]),
+        );
+        let momentum = Vector::vec_sum(
+            Vector::vec_scaled(velocities[survivor], masses[survivor]),
+            Vector::vec_scaled(velocities[other], masses[other]),
+        );
+
+        positions[survivor] = Vector::vec_scaled(weighted_position, 1.0 / mass);
+        velocities[survivor] = Vector::vec_scaled(momentum, 1.0 / mass);
+        radii[survivor] = (radii[survivor] * radii[survivor] + radii[other] * radii[other]).sqrt();
+        masses[survivor] = mass;
+        absorbed.push(other);
+    }
+
+    absorbed.sort_unstable();
+    absorbed
+}
+
+/// Detects and responds to every collision, returning the bodies absorbed by merging in ascending order
+pub fn resolve(
+    response: CollisionResponse,
+    positions: &mut [Vector<Float>],
+    velocities: &mut [Vector<Float>],
+    masses: &mut [Float],
+    radii: &mut [Float],
+) -> Vec<usize> {
+    match response {
+        CollisionResponse::Ignore => Vec::new(),
+        CollisionResponse::Elastic { restitution } => {
+            bounce(&contacts(positions, radii), positions, velocities, masses, restitution);
+            Vec::new()
+        },
+        CollisionResponse::Merge => merge(&contacts(positions, radii), positions, velocities, masses, radii),
+    }
+}
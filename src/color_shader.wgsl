@@ -18,6 +18,10 @@ struct Color {
 @group(0) @binding(0)
 var<uniform> transformation_matrix: mat4x4<f32>;
 
+// World to clip space, shared by every entity
+@group(2) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
 // Shader argument passed into here
 // Arrays don't work maybe - throws some error
 // Structs work though, so I assume there's no real point trying to make them work
@@ -29,7 +33,7 @@ fn vertex(
     vertex_input: VertexInput,
 ) -> VertexOutput {
     var output: VertexOutput;
-    output.position = vec4(vertex_input.position, 1.0, 1.0) * transformation_matrix;
+    output.position = vec4(vertex_input.position, 1.0, 1.0) * transformation_matrix * camera;
     output.color = shader_args.color;
     return output;
 }
@@ -0,0 +1,148 @@
+use std::{
+    fs::File,
+    io::{ self, BufWriter, Write },
+    path::Path
+};
+
+use crate::{
+    circle::Circle,
+    gravity::GravityConfig,
+    utils::{
+        defaults::Float,
+        Vector
+    }
+};
+
+/// Conserved quantities of a set of bodies at one instant. Sums are done in f64, so drift
+/// measured over long runs is the simulation's and not the measurement's
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostics {
+    pub kinetic_energy: f64,
+    // Gravitational, under the config it was measured with
+    pub potential_energy: f64,
+    pub momentum: [f64; 2],
+    // Around the origin
+    pub angular_momentum: f64,
+    pub center_of_mass: [f64; 2],
+    pub mass: f64,
+}
+
+impl Diagnostics {
+    /// Measures with the default gravity config
+    pub fn measure(positions: &[Vector<Float>], velocities: &[Vector<Float>], masses: &[Float]) -> Self {
+        Self::measure_with(&GravityConfig::default(), positions, velocities, masses)
+    }
+
+    /// Measures with the potential energy of `config`, which should be the one the bodies were simulated with
+    pub fn measure_with(config: &GravityConfig, positions: &[Vector<Float>], velocities: &[Vector<Float>], masses: &[Float]) -> Self {
+        let mut diagnostics = Self {
+            kinetic_energy: 0.0,
+            potential_energy: 0.0,
+            momentum: [0.0; 2],
+            angular_momentum: 0.0,
+            center_of_mass: [0.0; 2],
+            mass: 0.0,
+        };
+
+        for i in 0..positions.len() {
+            let mass = masses[i] as f64;
+            let (x, y) = (positions[i].x() as f64, positions[i].y() as f64);
+            let (vx, vy) = (velocities[i].x() as f64, velocities[i].y() as f64);
+
+            diagnostics.mass += mass;
+            diagnostics.kinetic_energy += 0.5 * mass * (vx * vx + vy * vy);
+            diagnostics.momentum[0] += mass * vx;
+            diagnostics.momentum[1] += mass * vy;
+            diagnostics.angular_momentum += mass * (x * vy - y * vx);
+            diagnostics.center_of_mass[0] += mass * x;
+            diagnostics.center_of_mass[1] += mass * y;
+
+            for j in i + 1..positions.len() {
+                let dx = positions[j].x() as f64 - x;
+                let dy = positions[j].y() as f64 - y;
+                diagnostics.potential_energy += config.potential((dx * dx + dy * dy).sqrt(), mass, masses[j] as f64);
+            }
+        }
+
+        if diagnostics.mass > 0.0 {
+            diagnostics.center_of_mass[0] /= diagnostics.mass;
+            diagnostics.center_of_mass[1] /= diagnostics.mass;
+        }
+
+        diagnostics
+    }
+
+    pub fn of_circles(config: &GravityConfig, circles: &[Circle]) -> Self {
+        let positions: Vec<Vector<Float>> = circles.iter().map(Circle::position).collect();
+        let velocities: Vec<Vector<Float>> = circles.iter().map(Circle::velocity).collect();
+        let masses: Vec<Float> = circles.iter().map(|circle| circle.mass() as Float).collect();
+        Self::measure_with(config, &positions, &velocities, &masses)
+    }
+
+    pub fn total_energy(&self) -> f64 {
+        self.kinetic_energy + self.potential_energy
+    }
+}
+
+/// Diagnostics recorded over a run, one sample per step
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsLog {
+    samples: Vec<(f64, Diagnostics)>,
+}
+
+impl DiagnosticsLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sample taken at `time` simulated seconds
+    pub fn record(&mut self, time: f64, diagnostics: Diagnostics) {
+        self.samples.push((time, diagnostics));
+    }
+
+    pub fn samples(&self) -> &[(f64, Diagnostics)] { &self.samples }
+
+    pub fn clear(&mut self) { self.samples.clear(); }
+
+    /// Largest change of the total energy relative to the first sample.
+    /// Absolute instead when the first sample has no energy, e.g. bodies at rest and out of each other's reach
+    pub fn energy_drift(&self) -> f64 {
+        let Some((_, first)) = self.samples.first() else { return 0.0; };
+        let initial = first.total_energy();
+        let scale = if initial == 0.0 { 1.0 } else { initial.abs() };
+        self.samples
+            .iter()
+            .map(|(_, sample)| (sample.total_energy() - initial).abs() / scale)
+            .fold(0.0, f64::max)
+    }
+
+    /// One row per sample, with a header naming the columns
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "time,kinetic_energy,potential_energy,total_energy,momentum_x,momentum_y,angular_momentum,center_of_mass_x,center_of_mass_y"
+        )?;
+        for (time, sample) in &self.samples {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{}",
+                time,
+                sample.kinetic_energy,
+                sample.potential_energy,
+                sample.total_energy(),
+                sample.momentum[0],
+                sample.momentum[1],
+                sample.angular_momentum,
+                sample.center_of_mass[0],
+                sample.center_of_mass[1],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_csv(&mut writer)?;
+        writer.flush()
+    }
+}
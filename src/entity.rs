@@ -1,58 +1,154 @@
 use std::{ 
-    rc::Rc, 
-    slice::{ 
-        IterMut, Iter 
-    } 
+    path::Path,
+    rc::Rc
 };
 
 use wgpu::util::DeviceExt;
 
 use crate::{
+    binary_mesh::{ BinaryMesh, BinaryMeshError },
+    camera::{ Camera, CameraMode },
+    mesh::{ Mesh, MeshError },
+    trail::TRAIL_LAYER,
     utils::{ 
         as_u8_slice, defaults::*, 
         Mat4x4, Vector 
-    }, utils
+    }, utils,
+    vertex::{ Attribute, VertexData, VertexFormat }
 };
 
 pub struct EntityList {
-    pub(crate) entities: Vec<Entity>,
+    // Deleted entities leave an empty slot, so the indices of the others stay valid
+    pub(crate) entities: Vec<Option<Entity>>,
+    // Empty slots, reused before the list grows
+    free: Vec<usize>,
+    // Bumped every time a slot is freed, so a kept index can tell it now points at a different entity
+    generations: Vec<u32>,
+    pub(crate) camera: Camera,
     pub(crate) device: Rc<wgpu::Device>,
     pub(crate) queue: Rc<wgpu::Queue>,
+    // Format of the texture the entities are drawn into, which every pipeline has to be built for
+    pub(crate) format: wgpu::TextureFormat,
 }
 
 impl EntityList {
-    pub fn new(device: Rc<wgpu::Device>, queue: Rc<wgpu::Queue>) -> Self {
-        Self { entities: vec![], device, queue }
+    pub fn new(device: Rc<wgpu::Device>, queue: Rc<wgpu::Queue>, format: wgpu::TextureFormat) -> Self {
+        Self { entities: vec![], free: vec![], generations: vec![], camera: Camera::new(&device), device, queue, format }
+    }
+
+    /// Format of the texture the entities are drawn into, for building pipelines of their own
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    pub(crate) fn update_camera(&mut self) {
+        self.camera.update(&self.entities, &self.queue);
     }
 
     pub fn add_entity(&mut self) -> &mut Entity {
-        let entity = Entity::default(self.device.clone(), self.queue.clone());
-        self.entities.push(entity);
-        return self.entities.last_mut().unwrap();
+        let mut entity = Entity::default(self.device.clone(), self.queue.clone());
+        entity.format = self.format;
+        let index = self.next_index();
+        match self.free.pop() {
+            Some(_) => self.entities[index] = Some(entity),
+            None => {
+                self.entities.push(Some(entity));
+                self.generations.push(0);
+            },
+        }
+        self.entities[index].as_mut().unwrap()
+    }
+
+    /// Index the next entity added will get
+    pub fn next_index(&self) -> usize {
+        self.free.last().copied().unwrap_or(self.entities.len())
+    }
+
+    /// Adds an entity drawing `mesh` in its vertex colors
+    pub fn add_mesh(&mut self, mesh: &Mesh) -> &mut Entity {
+        let entity = self.add_entity();
+        entity.set_mesh(mesh);
+        entity
+    }
+
+    /// Adds an entity drawing the mesh file at `path`, see `mesh` for the format
+    pub fn load_mesh(&mut self, path: impl AsRef<Path>) -> Result<&mut Entity, MeshError> {
+        let mesh = Mesh::load(path)?;
+        Ok(self.add_mesh(&mesh))
+    }
+
+    /// Adds an entity drawing the binary mesh file at `path`, see `binary_mesh` for the format
+    pub fn load_binary_mesh(&mut self, path: impl AsRef<Path>) -> Result<&mut Entity, BinaryMeshError> {
+        let mesh = BinaryMesh::load(path)?;
+        let entity = self.add_entity();
+        entity.set_binary_mesh(&mesh);
+        Ok(entity)
+    }
+
+    /// Adds an entity filling `path`
+    pub fn add_path(&mut self, path: &crate::path::Path) -> &mut Entity {
+        let entity = self.add_entity();
+        entity.set_path(path);
+        entity
     }
 
     pub fn get_entity(&mut self, index: usize) -> Option<&mut Entity> {
-        self.entities.get_mut(index)
+        self.entities.get_mut(index).and_then(Option::as_mut)
     }
 
     pub fn get_entity_unchecked(&mut self, index: usize) -> &mut Entity {
-        &mut self.entities[index]
+        self.entities[index].as_mut().expect("The entity was deleted")
+    }
+
+    /// Generation of the entity at `index`, if there is one. It changes whenever the slot is freed,
+    /// so an index kept together with its generation can't end up pointing at the entity that took its place
+    pub fn generation(&self, index: usize) -> Option<u32> {
+        self.entities.get(index)?.as_ref().map(|_| self.generations[index])
     }
 
+    /// Frees the entity and its GPU resources. The indices of the other entities don't change,
+    /// and the next entity added takes its place. A camera following it is set free
     pub fn delete_entity(&mut self, index: usize) {
-        self.entities.remove(index);
+        if self.entities.get_mut(index).and_then(Option::take).is_some() {
+            self.free.push(index);
+            self.generations[index] = self.generations[index].wrapping_add(1);
+            if self.camera.mode() == CameraMode::FollowEntity(index) {
+                self.camera.set_mode(CameraMode::Free);
+            }
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.entities.iter_mut().flatten()
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<'_, Entity>{
-        self.entities.iter_mut()
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter().flatten()
     }
 
-    pub fn iter(&mut self) -> Iter<'_, Entity>{
-        self.entities.iter()
+    /// Index of the first entity after `after` worth following with the camera, wrapping around.
+    /// Trails and entities with nothing to draw are skipped
+    pub fn next_followable(&self, after: Option<usize>) -> Option<usize> {
+        let start = after.map_or(0, |index| index + 1);
+        (0..self.entities.len())
+            .map(|offset| (start + offset) % self.entities.len())
+            .find(|&index| match &self.entities[index] {
+                Some(entity) => entity.layer != TRAIL_LAYER && entity.instance_count > 0,
+                None => false,
+            })
     }
 
+    /// Number of entities that haven't been deleted
     pub fn count(&self) -> usize {
-        self.entities.len()
+        self.entities.len() - self.free.len()
     }
     
 }
@@ -69,23 +165,40 @@ pub struct Entity {
     pub(crate) device: Rc<wgpu::Device>,
     pub(crate) queue: Rc<wgpu::Queue>,
     pub(crate) render_pipeline: Option<wgpu::RenderPipeline>,
+    // Bound to vertex buffer slot 1 when drawing, for pipelines with per-instance data
+    pub(crate) instance_buffer: Option<Rc<wgpu::Buffer>>,
+    pub(crate) instance_count: u32,
+    // Lower layers are drawn first, behind higher ones
+    pub(crate) layer: i32,
+    pub(crate) vertex_format: VertexFormat,
+    pub(crate) pipeline_source: PipelineSource,
+    // Format pipelines are built for, set by the entity list it is added to
+    pub(crate) format: wgpu::TextureFormat,
+}
+
+// Where an entity's pipeline came from, so it can be rebuilt when the vertex format changes
+pub(crate) enum PipelineSource {
+    // The renderer's for bare positions, a matching built-in one otherwise
+    Default,
+    // Given to `set_shader`
+    Shader(wgpu::ShaderModule),
+    // Given to `set_render_pipeline`, left alone
+    Custom,
 }
 
 impl Entity { 
 
     pub fn default(device: Rc<wgpu::Device>, queue: Rc<wgpu::Queue>) -> Self {
-        Self::new(&vec![], &vec![], Mat4x4::identity(), device, queue)
+        Self::new(&[], &[], Mat4x4::identity(), device, queue)
     }
 
     pub fn new(
-        vertex_data: &Vec<Vector<Float>>, 
-        index_data: &Vec<Index>, 
+        vertex_data: &[Vector<Float>], 
+        index_data: &[Index], 
         transform: Mat4x4,
         device: Rc<wgpu::Device>, 
         queue: Rc<wgpu::Queue>,
     ) -> Self {
-        let (vertex_slice, index_slice) = (vertex_data.as_slice(), index_data.as_slice());
-         
         let transform_buffer = Self::transform_buffer(&device, transform);
         let shader_buffer = Self::shader_args_buffer::<[f32; 1]>(&device, [0.0]);
         
@@ -116,12 +229,12 @@ impl Entity {
             index_size: index_data.len() as Index,
             vertex_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex buffer"),
-                contents: as_u8_slice(vertex_slice),
+                contents: as_u8_slice(vertex_data),
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             }),
             index_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Index buffer"),
-                contents: as_u8_slice(index_slice),
+                contents: as_u8_slice(index_data),
                 usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             }),
             transform_buffer,
@@ -129,26 +242,83 @@ impl Entity {
             transform_bind_group,
             shader_bind_group,
             render_pipeline: None,
+            instance_buffer: None,
+            instance_count: 1,
+            layer: 0,
+            vertex_format: VertexFormat::position(),
+            pipeline_source: PipelineSource::Default,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
             transform,
             device,
             queue,
         }
     }
 
-    pub fn set_geometry(&mut self, vertices: &Vec<Vector<Float>>, indices: &Vec<Index>) {
-        self.index_size = indices.len() as Index;
+    pub fn set_geometry(&mut self, vertices: &[Vector<Float>], indices: &[Index]) {
+        self.upload_geometry(as_u8_slice(vertices), as_u8_slice(indices), VertexFormat::position());
+    }
+
+    /// Like `set_geometry`, for vertices carrying more than positions.
+    /// Unless the pipeline was set with `set_render_pipeline`, it is rebuilt to match the new format,
+    /// so colored vertices are drawn in their colors without a shader of their own
+    pub fn set_vertex_data(&mut self, vertices: &VertexData, indices: &[Index]) {
+        self.upload_geometry(&vertices.bytes(), as_u8_slice(indices), vertices.format());
+    }
+
+    /// Uploads the vertices and indices straight from the mesh's bytes
+    pub fn set_binary_mesh(&mut self, mesh: &BinaryMesh) {
+        self.upload_geometry(mesh.vertex_bytes(), mesh.index_bytes(), mesh.format().clone());
+    }
+
+    pub fn set_mesh(&mut self, mesh: &Mesh) {
+        self.set_vertex_data(&mesh.vertex_data(), &mesh.indices);
+    }
+
+    /// Fills the path, flattened to the tolerance it was built with
+    pub fn set_path(&mut self, path: &crate::path::Path) {
+        let (vertices, indices) = path.fill();
+        self.set_geometry(&vertices, &indices);
+    }
+
+    pub fn vertex_format(&self) -> &VertexFormat { &self.vertex_format }
+
+    fn upload_geometry(&mut self, vertices: &[u8], indices: &[u8], format: VertexFormat) {
+        self.index_size = (indices.len() / std::mem::size_of::<Index>()) as Index;
 
         self.index_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Index buffer"),
-            contents: as_u8_slice(indices),
+            contents: indices,
             usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
         });
 
         self.vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex buffer"),
-            contents: as_u8_slice(vertices),
+            contents: vertices,
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
+
+        if format != self.vertex_format {
+            self.vertex_format = format;
+            self.rebuild_pipeline();
+        }
+    }
+
+    /// Overwrites the start of the vertex buffer with bare positions, which has to be large enough already
+    pub fn write_vertices(&mut self, vertices: &[Vector<Float>]) {
+        self.queue.write_buffer(&self.vertex_buffer, 0, as_u8_slice(vertices));
+    }
+
+    /// Draws only the first `count` indices of the index buffer
+    pub fn set_index_count(&mut self, count: Index) {
+        self.index_size = count;
+    }
+
+    pub fn layer(&self) -> i32 { self.layer }
+
+    /// Entities on lower layers are drawn behind the ones on higher layers, the default is 0.
+    /// Within a layer they are drawn in the order they were added
+    pub fn set_layer(&mut self, layer: i32) {
+        self.layer = layer;
     }
 
     fn send_transform(&mut self, transform: Mat4x4) {
@@ -203,17 +373,59 @@ impl Entity {
 
     pub fn get_transform(&self) -> Mat4x4 { self.transform }
     
-    pub fn set_transform(&mut self, new_transform: Mat4x4) { self.transform = new_transform; }
+    pub fn set_transform(&mut self, new_transform: Mat4x4) {
+        self.transform = new_transform;
+        self.send_transform(self.transform);
+    }
 
+    /// The shader reads the attributes of the vertex format at their locations, see `vertex::Attribute`
     pub fn set_shader(&mut self, shader: wgpu::ShaderModuleDescriptor) {
-        self.render_pipeline = 
-            Some(
-                utils::generate_render_pipeline(
-                    &self.device, 
-                    wgpu::TextureFormat::Rgba8UnormSrgb, 
-                    self.device.create_shader_module(shader)
-                )
-            );
+        self.pipeline_source = PipelineSource::Shader(self.device.create_shader_module(shader));
+        self.rebuild_pipeline();
+    }
+
+    /// Replaces the pipeline, for shaders that need more than `set_shader` sets up.
+    /// It must use the transform and shader arguments bind group layouts from `utils`
+    pub fn set_render_pipeline(&mut self, render_pipeline: wgpu::RenderPipeline) {
+        self.render_pipeline = Some(render_pipeline);
+        self.pipeline_source = PipelineSource::Custom;
+    }
+
+    fn rebuild_pipeline(&mut self) {
+        let built_in;
+        let shader = match &self.pipeline_source {
+            PipelineSource::Custom => return,
+            PipelineSource::Shader(shader) => shader,
+            PipelineSource::Default if self.vertex_format == VertexFormat::position() => {
+                self.render_pipeline = None;
+                return;
+            }
+            PipelineSource::Default => {
+                built_in = if self.vertex_format.has(Attribute::Color) {
+                    self.device.create_shader_module(wgpu::include_wgsl!("vertex_color_shader.wgsl"))
+                } else {
+                    self.device.create_shader_module(wgpu::include_wgsl!("base_shader.wgsl"))
+                };
+                &built_in
+            }
+        };
+
+        self.render_pipeline = Some(utils::generate_render_pipeline_for_format(
+            &self.device,
+            self.format,
+            shader,
+            &self.vertex_format
+        ));
+    }
+
+    /// Draws the geometry `count` times, with `buffer` as the per-instance vertex buffer
+    pub fn set_instances(&mut self, buffer: Rc<wgpu::Buffer>, count: u32) {
+        self.instance_buffer = Some(buffer);
+        self.instance_count = count;
+    }
+
+    pub fn set_instance_count(&mut self, count: u32) {
+        self.instance_count = count;
     }
 
     pub fn set_shader_args<T>(&mut self, args: T) {
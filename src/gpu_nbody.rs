@@ -0,0 +1,305 @@
+use std::rc::Rc;
+
+use wgpu::{
+    util::DeviceExt,
+    VertexAttribute
+};
+
+use crate::{
+    entity::EntityList,
+    gravity::GravityConfig,
+    utils::{
+        self, as_u8_slice,
+        defaults::{ Float, Index },
+        Vector
+    }
+};
+
+/// A body as laid out in the GPU's storage buffer
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GpuBody {
+    // Linear RGBA
+    pub color: [f32; 4],
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub mass: f32,
+    pub radius: f32,
+    _padding: [f32; 2],
+}
+
+impl GpuBody {
+    // Color, position and radius are read when drawing, as instance attributes
+    const INSTANCE_ATTRIBUTES: [VertexAttribute; 3] = [
+        VertexAttribute { format: wgpu::VertexFormat::Float32x4, offset: 0, shader_location: 1 },
+        VertexAttribute { format: wgpu::VertexFormat::Float32x2, offset: 16, shader_location: 2 },
+        VertexAttribute { format: wgpu::VertexFormat::Float32, offset: 36, shader_location: 3 },
+    ];
+
+    pub fn new(mass: Float, radius: Float, position: Vector<Float>, velocity: Vector<Float>, color: [f32; 3]) -> Self {
+        Self {
+            color: [color[0], color[1], color[2], 1.0],
+            position: position.pos,
+            velocity: velocity.pos,
+            mass,
+            radius,
+            _padding: [0.0; 2],
+        }
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GpuBody>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::INSTANCE_ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Params {
+    dt: f32,
+    g: f32,
+    softening: f32,
+    count: u32,
+    // 0 when unset
+    max_force: f32,
+    cutoff: f32,
+    _padding: [f32; 2],
+}
+
+impl Params {
+    fn new(config: &GravityConfig, count: u32) -> Self {
+        Self {
+            dt: 0.0,
+            g: config.g,
+            softening: config.softening,
+            count,
+            max_force: config.max_force.unwrap_or(0.0),
+            cutoff: config.cutoff.unwrap_or(0.0),
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// N-body gravity run entirely on the GPU. The bodies never leave their storage buffer, which is
+/// drawn from directly as the instance buffer of an entity
+pub struct GpuNBody {
+    device: Rc<wgpu::Device>,
+    queue: Rc<wgpu::Queue>,
+    bodies: Rc<wgpu::Buffer>,
+    config: GravityConfig,
+    params: Params,
+    params_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    accelerate_pipeline: wgpu::ComputePipeline,
+    integrate_pipeline: wgpu::ComputePipeline,
+    index: usize,
+}
+
+impl GpuNBody {
+    /// Uploads `bodies` and adds the entity drawing them to `entity_list`
+    pub fn new(entity_list: &mut EntityList, bodies: &[GpuBody]) -> Self {
+        let device = entity_list.device.clone();
+        let queue = entity_list.queue.clone();
+
+        let bodies_buffer = Rc::new(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("N-body bodies buffer"),
+            // Storage buffers can't be empty
+            contents: if bodies.is_empty() { &[0; std::mem::size_of::<GpuBody>()] } else { as_u8_slice(bodies) },
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        }));
+
+        let accelerations_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("N-body accelerations buffer"),
+            size: (bodies.len().max(1) * std::mem::size_of::<[f32; 2]>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let config = GravityConfig::default();
+        let params = Params::new(&config, bodies.len() as u32);
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("N-body parameters buffer"),
+            contents: as_u8_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("N-body Bind Group Layout Desc"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("N-body Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: bodies_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: accelerations_buffer.as_entire_binding() },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("N-body Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let compute_shader = device.create_shader_module(wgpu::include_wgsl!("nbody_compute.wgsl"));
+        let compute_pipeline = |entry_point| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("N-body Compute Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &compute_shader,
+            entry_point,
+        });
+        let accelerate_pipeline = compute_pipeline("accelerate");
+        let integrate_pipeline = compute_pipeline("integrate");
+
+        // A unit circle, scaled by each body's radius in the shader
+        let verts = utils::generate_regular_geometry(20, 1.0, Vector::new(0.0, 0.0), 0.0);
+        let indices = utils::generate_triangles((0..verts.len() as Index).collect());
+
+        let format = entity_list.format();
+        let index = entity_list.next_index();
+        let entity = entity_list.add_entity();
+        entity.set_geometry(&verts, &indices);
+        entity.set_render_pipeline(
+            utils::generate_instanced_render_pipeline(
+                &device,
+                format,
+                device.create_shader_module(wgpu::include_wgsl!("instanced_shader.wgsl")),
+                GpuBody::desc()
+            )
+        );
+        entity.set_instances(bodies_buffer.clone(), bodies.len() as u32);
+
+        Self {
+            device,
+            queue,
+            bodies: bodies_buffer,
+            config,
+            params,
+            params_buffer,
+            bind_group,
+            accelerate_pipeline,
+            integrate_pipeline,
+            index,
+        }
+    }
+
+    /// Index of the entity drawing the bodies
+    pub fn entity_index(&self) -> usize { self.index }
+
+    pub fn count(&self) -> usize { self.params.count as usize }
+
+    pub fn config(&self) -> &GravityConfig { &self.config }
+
+    /// Applies from the next step on
+    pub fn set_config(&mut self, config: GravityConfig) {
+        self.params = Params::new(&config, self.params.count);
+        self.config = config;
+    }
+
+    /// Queues `steps` steps of `dt` seconds
+    pub fn step(&mut self, dt: Float, steps: u32) {
+        if steps == 0 || self.params.count == 0 { return; }
+
+        self.params.dt = dt;
+        self.queue.write_buffer(&self.params_buffer, 0, as_u8_slice(&[self.params]));
+
+        let workgroups = self.params.count.div_ceil(WORKGROUP_SIZE);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("N-body encoder"),
+        });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("N-body pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_bind_group(0, &self.bind_group, &[]);
+            for _ in 0..steps {
+                compute_pass.set_pipeline(&self.accelerate_pipeline);
+                compute_pass.dispatch_workgroups(workgroups, 1, 1);
+                compute_pass.set_pipeline(&self.integrate_pipeline);
+                compute_pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+        }
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Replaces every body, the count has to stay the same
+    pub fn write_bodies(&mut self, bodies: &[GpuBody]) {
+        assert_eq!(bodies.len(), self.count(), "The amount of bodies can't change");
+        self.queue.write_buffer(&self.bodies, 0, as_u8_slice(bodies));
+    }
+
+    /// Copies the bodies back to the CPU, waiting for every queued step. Slow, meant for debugging and diagnostics
+    pub fn read_bodies(&self) -> Vec<GpuBody> {
+        let size = (self.count() * std::mem::size_of::<GpuBody>()) as wgpu::BufferAddress;
+        if size == 0 { return Vec::new(); }
+
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("N-body readback buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("N-body readback encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&self.bodies, 0, &readback, 0, size);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().expect("Failed to map readback buffer");
+
+        let data = slice.get_mapped_range();
+        let bodies = data
+            .chunks_exact(std::mem::size_of::<GpuBody>())
+            .map(|chunk| unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const GpuBody) })
+            .collect();
+        drop(data);
+        readback.unmap();
+
+        bodies
+    }
+}
@@ -0,0 +1,244 @@
+use crate::utils::{
+    defaults::Float,
+    Vector
+};
+
+// In units per second squared
+pub const G: Float = 0.00036;
+
+/// How bodies pull on each other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GravityConfig {
+    pub g: Float,
+    // Plummer softening length, spreads every mass out so the pull falls back to 0 at 0 distance instead of blowing up
+    pub softening: Float,
+    // Largest force between two bodies, if any
+    pub max_force: Option<Float>,
+    // Bodies further apart than this don't pull on each other at all, if set
+    pub cutoff: Option<Float>,
+}
+
+impl Default for GravityConfig {
+    fn default() -> Self {
+        Self { g: G, softening: 0.0, max_force: None, cutoff: None }
+    }
+}
+
+impl GravityConfig {
+    /// Acceleration of a body of `mass` at `position` towards `other_mass` at `other_position`
+    pub fn acceleration(&self, position: Vector<Float>, mass: Float, other_position: Vector<Float>, other_mass: Float) -> Vector<Float> {
+        let pos_diff = Vector::vec_diff(other_position, position);
+        let pos_diff_mag_sq = pos_diff.x() * pos_diff.x() + pos_diff.y() * pos_diff.y();
+        if let Some(cutoff) = self.cutoff {
+            if pos_diff_mag_sq > cutoff * cutoff { return Vector::new(0.0, 0.0); }
+        }
+
+        let softened_sq = pos_diff_mag_sq + self.softening * self.softening;
+        // Per unit of distance, so `pos_diff` doesn't need normalizing
+        let mut acceleration_mag = self.g * other_mass / (softened_sq * softened_sq.sqrt());
+        if let Some(max_force) = self.max_force {
+            let force = acceleration_mag * pos_diff_mag_sq.sqrt() * mass;
+            if force > max_force {
+                acceleration_mag *= max_force / force;
+            }
+        }
+        Vector::vec_scaled(pos_diff, acceleration_mag)
+    }
+
+    /// Potential energy of a pair of bodies, consistent with the softened force
+    pub fn potential(&self, distance: f64, mass: f64, other_mass: f64) -> f64 {
+        if let Some(cutoff) = self.cutoff {
+            if distance > cutoff as f64 { return 0.0; }
+        }
+        let softening = self.softening as f64;
+        -(self.g as f64) * mass * other_mass / (distance * distance + softening * softening).sqrt()
+    }
+}
+
+pub trait GravitySolver {
+    /// Fills `accelerations` with the gravitational acceleration of every body towards all the others
+    fn accelerations(&mut self, positions: &[Vector<Float>], masses: &[Float], accelerations: &mut [Vector<Float>]);
+
+    fn config(&self) -> &GravityConfig;
+
+    fn set_config(&mut self, config: GravityConfig);
+}
+
+/// Exact, but O(n²)
+#[derive(Debug, Clone, Default)]
+pub struct BruteForce {
+    pub config: GravityConfig,
+}
+
+impl BruteForce {
+    pub fn new(config: GravityConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl GravitySolver for BruteForce {
+    fn accelerations(&mut self, positions: &[Vector<Float>], masses: &[Float], accelerations: &mut [Vector<Float>]) {
+        for (i, acceleration) in accelerations.iter_mut().enumerate() {
+            *acceleration = Vector::new(0.0, 0.0);
+            for j in 0..positions.len() {
+                if i == j { continue; }
+                acceleration.add_vec(self.config.acceleration(positions[i], masses[i], positions[j], masses[j]));
+            }
+        }
+    }
+
+    fn config(&self) -> &GravityConfig { &self.config }
+
+    fn set_config(&mut self, config: GravityConfig) { self.config = config; }
+}
+
+const NO_CHILD: u32 = u32::MAX;
+// Bodies closer together than the cells at this depth share a leaf
+const MAX_DEPTH: u32 = 24;
+
+struct Node {
+    mass: Float,
+    mass_center: Vector<Float>,
+    // Side length of the cell
+    size: Float,
+    children: [u32; 4],
+    // Range of `BarnesHut::order` holding the bodies in this cell
+    start: usize,
+    end: usize,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.children == [NO_CHILD; 4]
+    }
+}
+
+/// Approximates the pull of far away groups of bodies by their center of mass, O(n log n).
+/// A cell of side `s` at distance `d` is approximated when `s / d < theta`, 0 making it exact
+pub struct BarnesHut {
+    pub theta: Float,
+    pub config: GravityConfig,
+    nodes: Vec<Node>,
+    order: Vec<usize>,
+    // Where each body ended up in `order`, to tell whether a cell contains it
+    rank: Vec<usize>,
+}
+
+impl BarnesHut {
+    pub fn new(theta: Float) -> Self {
+        Self { theta, config: GravityConfig::default(), nodes: Vec::new(), order: Vec::new(), rank: Vec::new() }
+    }
+
+    fn build(&mut self, positions: &[Vector<Float>], masses: &[Float]) {
+        self.nodes.clear();
+        self.order.clear();
+        self.order.extend(0..positions.len());
+        if positions.is_empty() { return; }
+
+        let (mut min, mut max) = (positions[0], positions[0]);
+        for position in positions {
+            min = Vector::new(min.x().min(position.x()), min.y().min(position.y()));
+            max = Vector::new(max.x().max(position.x()), max.y().max(position.y()));
+        }
+        let size = (max.x() - min.x()).max(max.y() - min.y()).max(Float::EPSILON);
+        let center = Vector::new((min.x() + max.x()) * 0.5, (min.y() + max.y()) * 0.5);
+
+        self.build_node(positions, masses, center, size, 0, positions.len(), 0);
+
+        self.rank.resize(positions.len(), 0);
+        for (rank, &body) in self.order.iter().enumerate() {
+            self.rank[body] = rank;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_node(
+        &mut self,
+        positions: &[Vector<Float>],
+        masses: &[Float],
+        center: Vector<Float>,
+        size: Float,
+        start: usize,
+        end: usize,
+        depth: u32,
+    ) -> u32 {
+        let mut mass = 0.0;
+        let mut weighted = Vector::new(0.0, 0.0);
+        for &body in &self.order[start..end] {
+            mass += masses[body];
+            weighted.add_vec(Vector::vec_scaled(positions[body], masses[body]));
+        }
+        let mass_center = if mass > 0.0 { Vector::vec_scaled(weighted, 1.0 / mass) } else { center };
+
+        let index = self.nodes.len() as u32;
+        self.nodes.push(Node { mass, mass_center, size, children: [NO_CHILD; 4], start, end });
+
+        if end - start <= 1 || depth >= MAX_DEPTH {
+            return index;
+        }
+
+        let quadrant = |position: Vector<Float>| {
+            (position.x() >= center.x()) as usize | (((position.y() >= center.y()) as usize) << 1)
+        };
+        self.order[start..end].sort_unstable_by_key(|&body| quadrant(positions[body]));
+
+        let mut children = [NO_CHILD; 4];
+        let mut child_start = start;
+        for (child, slot) in children.iter_mut().enumerate() {
+            let mut child_end = child_start;
+            while child_end < end && quadrant(positions[self.order[child_end]]) == child {
+                child_end += 1;
+            }
+            if child_end > child_start {
+                let offset = size * 0.25;
+                let child_center = Vector::new(
+                    center.x() + if child & 1 != 0 { offset } else { -offset },
+                    center.y() + if child & 2 != 0 { offset } else { -offset },
+                );
+                *slot = self.build_node(positions, masses, child_center, size * 0.5, child_start, child_end, depth + 1);
+            }
+            child_start = child_end;
+        }
+        self.nodes[index as usize].children = children;
+
+        index
+    }
+}
+
+impl GravitySolver for BarnesHut {
+    fn accelerations(&mut self, positions: &[Vector<Float>], masses: &[Float], accelerations: &mut [Vector<Float>]) {
+        self.build(positions, masses);
+
+        let theta_sq = self.theta * self.theta;
+        let mut stack = Vec::new();
+        for (i, acceleration) in accelerations.iter_mut().enumerate() {
+            *acceleration = Vector::new(0.0, 0.0);
+            if self.nodes.is_empty() { continue; }
+
+            stack.push(0);
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index as usize];
+
+                if node.is_leaf() {
+                    for &body in &self.order[node.start..node.end] {
+                        if body == i { continue; }
+                        acceleration.add_vec(self.config.acceleration(positions[i], masses[i], positions[body], masses[body]));
+                    }
+                    continue;
+                }
+
+                let distance_sq = Vector::vec_diff(node.mass_center, positions[i]).mag();
+                let contains_body = (node.start..node.end).contains(&self.rank[i]);
+                if !contains_body && node.size * node.size < theta_sq * distance_sq {
+                    acceleration.add_vec(self.config.acceleration(positions[i], masses[i], node.mass_center, node.mass));
+                } else {
+                    stack.extend(node.children.iter().copied().filter(|&child| child != NO_CHILD));
+                }
+            }
+        }
+    }
+
+    fn config(&self) -> &GravityConfig { &self.config }
+
+    fn set_config(&mut self, config: GravityConfig) { self.config = config; }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs::File,
+    io::{ self, BufWriter, Write },
+    path::Path
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    // Binary PPM (P6), the alpha channel is dropped
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+
+    /// Guesses the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            _ => None,
+        }
+    }
+}
+
+/// Writes tightly packed RGBA8 rows to `path`
+pub fn save_image(path: impl AsRef<Path>, format: ImageFormat, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let encoded = match format {
+        ImageFormat::Png => encode_png(width, height, rgba),
+        ImageFormat::Ppm => encode_ppm(width, height, rgba),
+    };
+    writer.write_all(&encoded)?;
+    writer.flush()
+}
+
+pub fn encode_ppm(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    data.reserve((width * height * 3) as usize);
+    for pixel in rgba.chunks(4) {
+        data.extend_from_slice(&pixel[..3]);
+    }
+    data
+}
+
+/// Reads a binary PPM (P6) with a max value of 255 back into RGBA8 rows, as `(width, height, rgba)`
+pub fn decode_ppm(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    // Header fields are separated by whitespace, with a single whitespace byte before the pixel data
+    let mut fields = Vec::with_capacity(4);
+    let mut i = 0;
+    while fields.len() < 4 {
+        while data.get(i)?.is_ascii_whitespace() { i += 1; }
+        // Comments run until the end of the line
+        if data[i] == b'#' {
+            while *data.get(i)? != b'\n' { i += 1; }
+            continue;
+        }
+        let start = i;
+        while !data.get(i)?.is_ascii_whitespace() { i += 1; }
+        fields.push(std::str::from_utf8(&data[start..i]).ok()?);
+    }
+
+    if fields[0] != "P6" || fields[3] != "255" { return None; }
+    let width = fields[1].parse::<u32>().ok()?;
+    let height = fields[2].parse::<u32>().ok()?;
+
+    let pixels = data.get(i + 1..i + 1 + (width * height * 3) as usize)?;
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for pixel in pixels.chunks(3) {
+        rgba.extend_from_slice(pixel);
+        rgba.push(255);
+    }
+    Some((width, height, rgba))
+}
+
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let row_size = (width * 4) as usize;
+
+    // Every scanline is prefixed with its filter type, 0 being no filter
+    let mut scanlines = Vec::with_capacity((row_size + 1) * height as usize);
+    for row in rgba.chunks(row_size) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, color type 6 (RGBA), default compression, filtering and no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    write_png_chunk(&mut png, b"IHDR", &header);
+    write_png_chunk(&mut png, b"IDAT", &zlib_compress(&scanlines));
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// CRC-32 as used by PNG chunks
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB88320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Adler-32 as used by zlib streams
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// Deflate bit writer, bits are packed starting from the least significant one
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u32,
+    filled: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, count: u32) {
+        for i in 0..count {
+            self.current |= ((value >> i) & 1) << self.filled;
+            self.filled += 1;
+            if self.filled == 8 {
+                self.bytes.push(self.current as u8);
+                self.current = 0;
+                self.filled = 0;
+            }
+        }
+    }
+
+    // Huffman codes are stored most significant bit first
+    fn write_code(&mut self, code: u32, length: u32) {
+        for i in (0..length).rev() {
+            self.write_bits((code >> i) & 1, 1);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.filled > 0 {
+            self.bytes.push(self.current as u8);
+        }
+        self.bytes
+    }
+}
+
+const LENGTH_BASES: [u32; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+const LENGTH_EXTRA_BITS: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+
+// Symbols of the fixed literal / length huffman table
+fn write_fixed_symbol(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + symbol - 280, 8),
+    }
+}
+
+// A single fixed-huffman deflate block. The only back-references emitted are to the previous pixel,
+// which is enough to shrink the flat backgrounds rendered frames are mostly made of
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    const DISTANCE: usize = 4;
+    const MAX_LENGTH: usize = 258;
+
+    let mut writer = BitWriter { bytes: vec![0x78, 0x01], current: 0, filled: 0 };
+    // Final block, fixed huffman codes
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut length = 0;
+        if i >= DISTANCE {
+            while length < MAX_LENGTH && i + length < data.len() && data[i + length] == data[i + length - DISTANCE] {
+                length += 1;
+            }
+        }
+
+        if length >= 3 {
+            let code = LENGTH_BASES.iter().rposition(|&base| base as usize <= length).unwrap();
+            write_fixed_symbol(&mut writer, 257 + code as u32);
+            writer.write_bits(length as u32 - LENGTH_BASES[code], LENGTH_EXTRA_BITS[code]);
+            // Distance code 3 is exactly 4 with no extra bits
+            writer.write_code(3, 5);
+            i += length;
+        } else {
+            write_fixed_symbol(&mut writer, data[i] as u32);
+            i += 1;
+        }
+    }
+    // End of block
+    write_fixed_symbol(&mut writer, 256);
+
+    let mut compressed = writer.finish();
+    compressed.extend_from_slice(&adler32(data).to_be_bytes());
+    compressed
+}
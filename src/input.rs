@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+
+use winit::{
+    event::{ ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent },
+    keyboard::{ KeyCode, PhysicalKey },
+};
+
+use crate::{
+    camera::Camera,
+    utils::{
+        defaults::Float,
+        Vector
+    }
+};
+
+/// Pixels of smooth scrolling that count as one notch of a wheel
+const PIXELS_PER_NOTCH: Float = 40.0;
+
+/// Keyboard and mouse as of the current frame, kept up to date by `BaseRenderer::run` and handed to the main loop.
+/// "Pressed" and "released" cover everything since the last frame, so short taps are never missed
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    keys_held: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    buttons_held: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    // In pixels from the top left corner of the window, `None` outside of it
+    mouse_position: Option<Vector<Float>>,
+    // Wheel notches since the last frame, positive away from the user
+    scroll: Float,
+}
+
+impl InputState {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn key_held(&self, key: KeyCode) -> bool { self.keys_held.contains(&key) }
+
+    pub fn key_pressed(&self, key: KeyCode) -> bool { self.keys_pressed.contains(&key) }
+
+    pub fn key_released(&self, key: KeyCode) -> bool { self.keys_released.contains(&key) }
+
+    pub fn button_held(&self, button: MouseButton) -> bool { self.buttons_held.contains(&button) }
+
+    pub fn button_pressed(&self, button: MouseButton) -> bool { self.buttons_pressed.contains(&button) }
+
+    pub fn button_released(&self, button: MouseButton) -> bool { self.buttons_released.contains(&button) }
+
+    pub fn mouse_position(&self) -> Option<Vector<Float>> { self.mouse_position }
+
+    /// Where the mouse is in the world as seen through `camera`
+    pub fn mouse_world(&self, camera: &Camera) -> Option<Vector<Float>> {
+        self.mouse_position.map(|pixel| camera.screen_to_world(pixel))
+    }
+
+    pub fn scroll_delta(&self) -> Float { self.scroll }
+
+    // The rest is driven by the renderer, but can also script input, e.g. for headless runs
+
+    /// Key repeats count as neither presses nor releases
+    pub fn press_key(&mut self, key: KeyCode) {
+        if self.keys_held.insert(key) {
+            self.keys_pressed.insert(key);
+        }
+    }
+
+    pub fn release_key(&mut self, key: KeyCode) {
+        if self.keys_held.remove(&key) {
+            self.keys_released.insert(key);
+        }
+    }
+
+    pub fn press_button(&mut self, button: MouseButton) {
+        if self.buttons_held.insert(button) {
+            self.buttons_pressed.insert(button);
+        }
+    }
+
+    pub fn release_button(&mut self, button: MouseButton) {
+        if self.buttons_held.remove(&button) {
+            self.buttons_released.insert(button);
+        }
+    }
+
+    pub fn set_mouse_position(&mut self, position: Option<Vector<Float>>) {
+        self.mouse_position = position;
+    }
+
+    pub fn scroll_by(&mut self, notches: Float) { self.scroll += notches; }
+
+    /// Lets go of every key and button, e.g. when the window loses focus and their releases would go unseen
+    pub fn release_all(&mut self) {
+        self.keys_released.extend(self.keys_held.drain());
+        self.buttons_released.extend(self.buttons_held.drain());
+    }
+
+    /// Forgets what happened since the last frame, keeping what is still held
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.scroll = 0.0;
+    }
+
+    pub(crate) fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent { physical_key: PhysicalKey::Code(key), state, .. },
+                ..
+            } => match state {
+                ElementState::Pressed => self.press_key(*key),
+                ElementState::Released => self.release_key(*key),
+            },
+            WindowEvent::MouseInput { button, state, .. } => match state {
+                ElementState::Pressed => self.press_button(*button),
+                ElementState::Released => self.release_button(*button),
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_position = Some(Vector::new(position.x as Float, position.y as Float));
+            }
+            WindowEvent::CursorLeft { .. } => self.mouse_position = None,
+            WindowEvent::MouseWheel { delta, .. } => self.scroll_by(scroll_notches(*delta)),
+            WindowEvent::Focused(false) => self.release_all(),
+            _ => {}
+        }
+    }
+}
+
+/// Wheel movement in notches, however the platform reports it
+pub(crate) fn scroll_notches(delta: MouseScrollDelta) -> Float {
+    match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        // Roughly what a line is worth
+        MouseScrollDelta::PixelDelta(position) => position.y as Float / PIXELS_PER_NOTCH,
+    }
+}
@@ -0,0 +1,146 @@
+use std::rc::Rc;
+
+use wgpu::VertexAttribute;
+
+use crate::{
+    entity::EntityList,
+    utils::{
+        self, as_u8_slice,
+        defaults::{ Float, Index },
+        Mat4x4, Vector
+    }
+};
+
+/// Transform and color of one copy of an `Instanced` mesh
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Instance {
+    pub transform: Mat4x4,
+    // Linear RGBA
+    pub color: [f32; 4],
+}
+
+impl Instance {
+    // The transform is passed as its 4 rows, the shader puts them back together
+    const ATTRIBUTES: [VertexAttribute; 5] = wgpu::vertex_attr_array![
+        1 => Float32x4, 2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4
+    ];
+
+    pub fn new(position: Vector<Float>, color: [f32; 3]) -> Self {
+        let mut transform = Mat4x4::identity();
+        transform.translate_to(position);
+        Self { transform, color: [color[0], color[1], color[2], 1.0] }
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// One mesh drawn many times with a single draw call, each copy with its own transform and color.
+/// The entity's own transform still applies, on top of every instance's
+pub struct Instanced {
+    index: usize,
+    instances: Vec<Instance>,
+    buffer: Rc<wgpu::Buffer>,
+    // Instances the buffer has room for
+    capacity: usize,
+    // Whether `instances` changed since the last `upload`
+    dirty: bool,
+}
+
+impl Instanced {
+    /// Adds the entity drawing the copies of the mesh to `entity_list`
+    pub fn new(entity_list: &mut EntityList, vertices: &[Vector<Float>], indices: &[Index]) -> Self {
+        let device = entity_list.device.clone();
+        let capacity = 64;
+        let buffer = Rc::new(Self::instance_buffer(&device, capacity));
+
+        let format = entity_list.format();
+        let index = entity_list.next_index();
+        let entity = entity_list.add_entity();
+        entity.set_geometry(vertices, indices);
+        entity.set_render_pipeline(
+            utils::generate_instanced_render_pipeline(
+                &device,
+                format,
+                device.create_shader_module(wgpu::include_wgsl!("instanced_transform_shader.wgsl")),
+                Instance::desc()
+            )
+        );
+        entity.set_instances(buffer.clone(), 0);
+
+        Self { index, instances: Vec::new(), buffer, capacity, dirty: false }
+    }
+
+    /// Index of the entity drawing the instances
+    pub fn entity_index(&self) -> usize { self.index }
+
+    pub fn len(&self) -> usize { self.instances.len() }
+
+    pub fn is_empty(&self) -> bool { self.instances.is_empty() }
+
+    /// Adds an instance, returning its index
+    pub fn push(&mut self, instance: Instance) -> usize {
+        self.instances.push(instance);
+        self.dirty = true;
+        self.instances.len() - 1
+    }
+
+    /// Removes an instance, the last one takes its index
+    pub fn swap_remove(&mut self, index: usize) -> Instance {
+        self.dirty = true;
+        self.instances.swap_remove(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.instances.clear();
+        self.dirty = true;
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Instance> {
+        self.instances.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Instance> {
+        self.dirty = true;
+        self.instances.get_mut(index)
+    }
+
+    pub fn instances(&self) -> &[Instance] { &self.instances }
+
+    pub fn instances_mut(&mut self) -> &mut [Instance] {
+        self.dirty = true;
+        &mut self.instances
+    }
+
+    /// Sends the instances to the GPU, growing the buffer when they don't fit anymore.
+    /// Nothing is sent when they haven't changed since the last call
+    pub fn upload(&mut self, entity_list: &mut EntityList) {
+        if !self.dirty { return; }
+        self.dirty = false;
+
+        let entity = entity_list.get_entity_unchecked(self.index);
+        if self.instances.len() > self.capacity {
+            self.capacity = self.instances.len().next_power_of_two();
+            self.buffer = Rc::new(Self::instance_buffer(&entity.device, self.capacity));
+            entity.set_instances(self.buffer.clone(), 0);
+        }
+
+        entity.queue.write_buffer(&self.buffer, 0, as_u8_slice(&self.instances));
+        entity.set_instance_count(self.instances.len() as u32);
+    }
+
+    fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance buffer"),
+            size: (capacity * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+}
+
+// Per instance
+struct InstanceInput {
+    @location(1) color: vec4<f32>,
+    @location(2) position: vec2<f32>,
+    @location(3) scale: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> transformation_matrix: mat4x4<f32>;
+
+// World to clip space, shared by every entity
+@group(2) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+@vertex
+fn vertex(
+    vertex_input: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var output: VertexOutput;
+    let position = vertex_input.position * instance.scale + instance.position;
+    output.position = vec4(position, 1.0, 1.0) * transformation_matrix * camera;
+    output.color = instance.color.rgb;
+    return output;
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> FragmentOutput {
+    var output: FragmentOutput;
+    output.color = vec4(input.color, 1.0);
+    return output;
+}
@@ -0,0 +1,53 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+}
+
+// Per instance
+struct InstanceInput {
+    @location(1) transform_0: vec4<f32>,
+    @location(2) transform_1: vec4<f32>,
+    @location(3) transform_2: vec4<f32>,
+    @location(4) transform_3: vec4<f32>,
+    @location(5) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> transformation_matrix: mat4x4<f32>;
+
+// World to clip space, shared by every entity
+@group(2) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+@vertex
+fn vertex(
+    vertex_input: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var output: VertexOutput;
+    // Laid out the same way as the uniform, so it is applied the same way
+    let instance_matrix = mat4x4<f32>(
+        instance.transform_0,
+        instance.transform_1,
+        instance.transform_2,
+        instance.transform_3,
+    );
+    output.position = vec4(vertex_input.position, 1.0, 1.0) * instance_matrix * transformation_matrix * camera;
+    output.color = instance.color.rgb;
+    return output;
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> FragmentOutput {
+    var output: FragmentOutput;
+    output.color = vec4(input.color, 1.0);
+    return output;
+}
@@ -0,0 +1,245 @@
+use crate::utils::{
+    defaults::Float,
+    Vector
+};
+
+/// Fills the second slice with the acceleration of every body, given their positions
+pub type AccelerationFn<'a> = dyn FnMut(&[Vector<Float>], &mut [Vector<Float>]) + 'a;
+
+pub trait Integrator {
+    /// Advances `positions` and `velocities` by `dt` seconds
+    fn step(
+        &mut self,
+        positions: &mut [Vector<Float>],
+        velocities: &mut [Vector<Float>],
+        dt: Float,
+        acceleration: &mut AccelerationFn,
+    );
+
+    fn name(&self) -> &'static str;
+}
+
+/// Looks an integrator up by the name it reports
+pub fn by_name(name: &str) -> Option<Box<dyn Integrator>> {
+    match name {
+        "explicit_euler" => Some(Box::new(ExplicitEuler)),
+        "symplectic_euler" => Some(Box::new(SymplecticEuler)),
+        "velocity_verlet" => Some(Box::new(VelocityVerlet)),
+        "rk4" => Some(Box::new(RungeKutta4)),
+        "rk45" => Some(Box::new(RungeKutta45::default())),
+        _ => None,
+    }
+}
+
+// values[i] += derivatives[i] * factor
+fn add_scaled(values: &mut [Vector<Float>], derivatives: &[Vector<Float>], factor: Float) {
+    for (value, derivative) in values.iter_mut().zip(derivatives) {
+        value.add_vec(Vector::vec_scaled(*derivative, factor));
+    }
+}
+
+// Derivative of every body, for each stage of a Runge-Kutta method
+type Stages = Vec<Vec<Vector<Float>>>;
+
+fn zeroed(len: usize) -> Vec<Vector<Float>> {
+    vec![Vector::new(0.0, 0.0); len]
+}
+
+/// Position first, with the old velocity. Gains energy on orbits, kept around for comparison
+pub struct ExplicitEuler;
+
+impl Integrator for ExplicitEuler {
+    fn step(&mut self, positions: &mut [Vector<Float>], velocities: &mut [Vector<Float>], dt: Float, acceleration: &mut AccelerationFn) {
+        let mut accelerations = zeroed(positions.len());
+        acceleration(positions, &mut accelerations);
+
+        for ((position, velocity), acceleration) in positions.iter_mut().zip(velocities.iter_mut()).zip(&accelerations) {
+            position.add_vec(Vector::vec_scaled(*velocity, dt));
+            velocity.add_vec(Vector::vec_scaled(*acceleration, dt));
+        }
+    }
+
+    fn name(&self) -> &'static str { "explicit_euler" }
+}
+
+/// Velocity first, then position with the new velocity. First order, but symplectic
+pub struct SymplecticEuler;
+
+impl Integrator for SymplecticEuler {
+    fn step(&mut self, positions: &mut [Vector<Float>], velocities: &mut [Vector<Float>], dt: Float, acceleration: &mut AccelerationFn) {
+        let mut accelerations = zeroed(positions.len());
+        acceleration(positions, &mut accelerations);
+
+        for ((position, velocity), acceleration) in positions.iter_mut().zip(velocities.iter_mut()).zip(&accelerations) {
+            velocity.add_vec(Vector::vec_scaled(*acceleration, dt));
+            position.add_vec(Vector::vec_scaled(*velocity, dt));
+        }
+    }
+
+    fn name(&self) -> &'static str { "symplectic_euler" }
+}
+
+/// Kick-drift-kick leapfrog, second order and symplectic
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn step(&mut self, positions: &mut [Vector<Float>], velocities: &mut [Vector<Float>], dt: Float, acceleration: &mut AccelerationFn) {
+        let mut accelerations = zeroed(positions.len());
+
+        acceleration(positions, &mut accelerations);
+        add_scaled(velocities, &accelerations, dt * 0.5);
+
+        add_scaled(positions, velocities, dt);
+
+        acceleration(positions, &mut accelerations);
+        add_scaled(velocities, &accelerations, dt * 0.5);
+    }
+
+    fn name(&self) -> &'static str { "velocity_verlet" }
+}
+
+// Derivatives of every stage of an explicit Runge-Kutta method, `a` being the lower triangle of its tableau.
+// Returns the position derivatives (velocities) and velocity derivatives (accelerations) of each stage
+fn runge_kutta_stages(
+    a: &[&[Float]],
+    positions: &[Vector<Float>],
+    velocities: &[Vector<Float>],
+    dt: Float,
+    acceleration: &mut AccelerationFn,
+) -> (Stages, Stages) {
+    let len = positions.len();
+    let mut position_derivatives: Stages = Vec::with_capacity(a.len());
+    let mut velocity_derivatives: Stages = Vec::with_capacity(a.len());
+
+    let mut stage_positions = positions.to_vec();
+    let mut stage_velocities = velocities.to_vec();
+
+    for weights in a {
+        stage_positions.copy_from_slice(positions);
+        stage_velocities.copy_from_slice(velocities);
+        for (j, &weight) in weights.iter().enumerate() {
+            if weight == 0.0 { continue; }
+            add_scaled(&mut stage_positions, &position_derivatives[j], weight * dt);
+            add_scaled(&mut stage_velocities, &velocity_derivatives[j], weight * dt);
+        }
+
+        let mut accelerations = zeroed(len);
+        acceleration(&stage_positions, &mut accelerations);
+        position_derivatives.push(stage_velocities.clone());
+        velocity_derivatives.push(accelerations);
+    }
+
+    (position_derivatives, velocity_derivatives)
+}
+
+// Weighted sum of the stage derivatives, applied to the state
+fn runge_kutta_combine(
+    b: &[Float],
+    position_derivatives: &Stages,
+    velocity_derivatives: &Stages,
+    positions: &mut [Vector<Float>],
+    velocities: &mut [Vector<Float>],
+    dt: Float,
+) {
+    for (stage, &weight) in b.iter().enumerate() {
+        if weight == 0.0 { continue; }
+        add_scaled(positions, &position_derivatives[stage], weight * dt);
+        add_scaled(velocities, &velocity_derivatives[stage], weight * dt);
+    }
+}
+
+/// Classic fourth order Runge-Kutta
+pub struct RungeKutta4;
+
+impl Integrator for RungeKutta4 {
+    fn step(&mut self, positions: &mut [Vector<Float>], velocities: &mut [Vector<Float>], dt: Float, acceleration: &mut AccelerationFn) {
+        const A: [&[Float]; 4] = [&[], &[0.5], &[0.0, 0.5], &[0.0, 0.0, 1.0]];
+        const B: [Float; 4] = [1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0];
+
+        let (position_derivatives, velocity_derivatives) = runge_kutta_stages(&A, positions, velocities, dt, acceleration);
+        runge_kutta_combine(&B, &position_derivatives, &velocity_derivatives, positions, velocities, dt);
+    }
+
+    fn name(&self) -> &'static str { "rk4" }
+}
+
+/// Dormand-Prince 5(4). Splits every step into as many substeps as needed to keep the local error
+/// estimate within `absolute_tolerance + relative_tolerance * |state|`
+pub struct RungeKutta45 {
+    pub absolute_tolerance: Float,
+    pub relative_tolerance: Float,
+    // Substep size carried over between steps
+    substep: Option<Float>,
+}
+
+impl RungeKutta45 {
+    pub fn new(absolute_tolerance: Float, relative_tolerance: Float) -> Self {
+        Self { absolute_tolerance, relative_tolerance, substep: None }
+    }
+}
+
+impl Default for RungeKutta45 {
+    fn default() -> Self {
+        Self::new(1e-6, 1e-5)
+    }
+}
+
+impl Integrator for RungeKutta45 {
+    fn step(&mut self, positions: &mut [Vector<Float>], velocities: &mut [Vector<Float>], dt: Float, acceleration: &mut AccelerationFn) {
+        const A: [&[Float]; 7] = [
+            &[],
+            &[1.0 / 5.0],
+            &[3.0 / 40.0, 9.0 / 40.0],
+            &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+            &[19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0],
+            &[9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0],
+            &[35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+        ];
+        const B5: [Float; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+        const B4: [Float; 7] = [
+            5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0, 187.0 / 2100.0, 1.0 / 40.0
+        ];
+        // Below this fraction of `dt` substeps are taken regardless of the error
+        const MIN_SUBSTEP: Float = 1e-4;
+
+        let mut remaining = dt;
+        let mut substep = self.substep.unwrap_or(dt).min(dt);
+
+        while remaining > 0.0 {
+            let h = substep.min(remaining);
+            let (position_derivatives, velocity_derivatives) = runge_kutta_stages(&A, positions, velocities, h, acceleration);
+
+            let (mut positions5, mut velocities5) = (positions.to_vec(), velocities.to_vec());
+            let (mut positions4, mut velocities4) = (positions.to_vec(), velocities.to_vec());
+            runge_kutta_combine(&B5, &position_derivatives, &velocity_derivatives, &mut positions5, &mut velocities5, h);
+            runge_kutta_combine(&B4, &position_derivatives, &velocity_derivatives, &mut positions4, &mut velocities4, h);
+
+            // Largest error relative to its tolerance, accepted when at most 1.
+            // Infinite if the substep blew up, `max` would skip over a NaN
+            let mut error: Float = 0.0;
+            for (fifth, fourth) in positions5.iter().zip(&positions4).chain(velocities5.iter().zip(&velocities4)) {
+                for axis in 0..2 {
+                    let tolerance = self.absolute_tolerance + self.relative_tolerance * fifth.pos[axis].abs();
+                    let component = (fifth.pos[axis] - fourth.pos[axis]).abs() / tolerance;
+                    error = if component.is_finite() { error.max(component) } else { Float::INFINITY };
+                }
+            }
+
+            if error.is_finite() && (error <= 1.0 || h <= dt * MIN_SUBSTEP) {
+                positions.copy_from_slice(&positions5);
+                velocities.copy_from_slice(&velocities5);
+                remaining -= h;
+            } else if !error.is_finite() && h <= dt * MIN_SUBSTEP {
+                // Still blowing up at the smallest substep, the rest of the step is dropped rather than spreading NaNs
+                break;
+            }
+
+            let factor = if error == 0.0 { 5.0 } else { (0.9 * error.powf(-0.2)).clamp(0.2, 5.0) };
+            substep = (h * factor).max(dt * MIN_SUBSTEP);
+        }
+
+        self.substep = Some(substep);
+    }
+
+    fn name(&self) -> &'static str { "rk45" }
+}
@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+
+use winit::event::MouseButton;
+
+use crate::{
+    circle::Circle,
+    entity::EntityList,
+    input::InputState,
+    scene::BodyDescription,
+    trail::TrailStyle,
+    utils::{
+        defaults::Float,
+        Vector
+    }
+};
+
+/// How far back the cursor's movement counts towards the velocity a body is released with, in simulated seconds
+pub const FLING_WINDOW: Float = 0.1;
+
+/// Lets the mouse grab circles, drag them around and fling them, and spawn new ones where nothing is hit
+pub struct Interaction {
+    // Spawned bodies are copies of this, placed at the cursor
+    pub spawn: BodyDescription,
+    pub trail: Option<TrailStyle>,
+    // Entity index of the grabbed circle, which stays valid when other circles are removed,
+    // with its generation so a circle spawned into its slot after it was merged away isn't grabbed instead
+    grabbed: Option<(usize, u32)>,
+    target: Vector<Float>,
+    // Where the cursor was in the world on the last update
+    cursor: Vector<Float>,
+    // Where the grabbed circle was held, oldest first, with the simulated time since it was grabbed
+    samples: VecDeque<(Float, Vector<Float>)>,
+    time: Float,
+}
+
+impl Interaction {
+    pub fn new(spawn: BodyDescription, trail: Option<TrailStyle>) -> Self {
+        Self { spawn, trail, grabbed: None, target: Vector::new(0.0, 0.0), cursor: Vector::new(0.0, 0.0), samples: VecDeque::new(), time: 0.0 }
+    }
+
+    /// Entity index of the circle being dragged
+    pub fn grabbed(&self) -> Option<usize> { self.grabbed.map(|(index, _)| index) }
+
+    pub fn set_spawn_mass(&mut self, mass: usize) { self.spawn.mass = mass.max(1); }
+
+    /// Grabs the circle under `point`, or spawns one there and grabs that. Returns whether a circle was hit
+    pub fn grab_or_spawn(&mut self, circles: &mut Vec<Circle>, entity_list: &mut EntityList, point: Vector<Float>) -> bool {
+        let hit = Circle::pick(circles, point);
+        let index = hit.unwrap_or_else(|| {
+            let body = BodyDescription { position: point.pos, velocity: [0.0, 0.0], ..self.spawn };
+            circles.push(body.spawn(entity_list, self.trail));
+            circles.len() - 1
+        });
+
+        let entity_index = circles[index].entity_index();
+        self.grabbed = entity_list.generation(entity_index).map(|generation| (entity_index, generation));
+        self.samples.clear();
+        self.time = 0.0;
+        // The circle keeps its offset from the cursor instead of jumping onto it
+        self.target = circles[index].position();
+        self.samples.push_back((0.0, self.target));
+        hit.is_some()
+    }
+
+    /// Moves the grabbed circle along with the cursor, which moved by `delta`
+    pub fn drag_by(&mut self, circles: &mut [Circle], entity_list: &mut EntityList, delta: Vector<Float>) {
+        let Some(circle) = self.grabbed_circle(circles, entity_list) else { return; };
+        self.target.add_vec(delta);
+        circle.place(entity_list, self.target);
+    }
+
+    /// Pins the grabbed circle to the cursor after a step of `dt`, overriding whatever the step did to it,
+    /// and gives it the velocity it was dragged with
+    pub fn hold(&mut self, circles: &mut [Circle], entity_list: &mut EntityList, dt: Float) {
+        let target = self.target;
+        let Some(circle) = self.grabbed_circle(circles, entity_list) else {
+            self.grabbed = None;
+            return;
+        };
+
+        circle.place(entity_list, target);
+
+        self.time += dt;
+        self.samples.push_back((self.time, target));
+        while self.samples.len() > 2 && self.time - self.samples[1].0 >= FLING_WINDOW {
+            self.samples.pop_front();
+        }
+        circle.set_velocity(self.fling_velocity());
+    }
+
+    /// Lets go of the grabbed circle, which keeps the velocity it was dragged with
+    pub fn release(&mut self, circles: &mut [Circle], entity_list: &EntityList) {
+        let velocity = self.fling_velocity();
+        if let Some(circle) = self.grabbed_circle(circles, entity_list) {
+            circle.set_velocity(velocity);
+        }
+        self.grabbed = None;
+        self.samples.clear();
+    }
+
+    /// Velocity of the cursor over the last `FLING_WINDOW`
+    pub fn fling_velocity(&self) -> Vector<Float> {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(&(start, from)), Some(&(end, to))) if end > start => Vector::vec_scaled(Vector::vec_diff(to, from), 1.0 / (end - start)),
+            _ => Vector::new(0.0, 0.0),
+        }
+    }
+
+    /// Reacts to what the left mouse button did since the last frame. Call once per frame, and `hold` after every step
+    pub fn update(&mut self, circles: &mut Vec<Circle>, entity_list: &mut EntityList, input: &InputState) {
+        let world = input.mouse_world(entity_list.camera());
+
+        if let (true, Some(world)) = (input.button_pressed(MouseButton::Left), world) {
+            self.grab_or_spawn(circles, entity_list, world);
+            // The cursor is where it was grabbed, so the offset to it is kept from here on
+            self.cursor = world;
+        }
+        match (self.grabbed, world) {
+            (Some(_), Some(world)) => {
+                let delta = Vector::vec_diff(world, self.cursor);
+                self.drag_by(circles, entity_list, delta);
+                self.cursor = world;
+            }
+            // Out of the window, where the button going up may never be seen
+            (Some(_), None) => self.release(circles, entity_list),
+            _ => {}
+        }
+        if !input.button_held(MouseButton::Left) {
+            self.release(circles, entity_list);
+        }
+    }
+
+    fn grabbed_circle<'a>(&self, circles: &'a mut [Circle], entity_list: &EntityList) -> Option<&'a mut Circle> {
+        let (grabbed, generation) = self.grabbed?;
+        // Gone once merged into another circle, even if a new one took its slot since
+        if entity_list.generation(grabbed) != Some(generation) { return None; }
+        circles.iter_mut().find(|circle| circle.entity_index() == grabbed)
+    }
+}
@@ -0,0 +1,25 @@
+// Modules
+pub mod base_renderer;
+pub mod binary_mesh;
+pub mod camera;
+pub mod vertex;
+pub mod utils;
+pub mod entity;
+pub mod circle;
+pub mod collision;
+pub mod diagnostics;
+pub mod gpu_nbody;
+pub mod gravity;
+pub mod image;
+pub mod instanced;
+pub mod input;
+pub mod integrator;
+pub mod interaction;
+pub mod mesh;
+pub mod path;
+pub mod recording;
+pub mod scene;
+pub mod snapshot;
+pub mod timestep;
+pub mod trail;
+pub mod triangulate;
@@ -1,18 +1,17 @@
-// Modules
-mod base_renderer;
-mod vertex;
-mod utils;
-mod entity;
-mod circle;
-
 // Imports
-use base_renderer::BaseRenderer;
-use entity::EntityList;
-use utils::Vector;
-use circle::Circle;
+use renderer::{
+    base_renderer::BaseRenderer,
+    entity::EntityList,
+    input::InputState,
+    scene::Scene,
+    timestep::FrameTime,
+    circle::Circle,
+    interaction::Interaction,
+};
 use winit::{
     dpi::PhysicalSize, 
     event_loop::EventLoop, 
+    keyboard::KeyCode, 
     window::{
         Theme, 
         WindowBuilder
@@ -20,10 +19,22 @@ use winit::{
 };
 use pollster::FutureExt as _;
 
+// Loaded when no scene is given on the command line
+const DEFAULT_SCENE: &str = "scenes/three_bodies.scene";
+
 fn main() {
     
     env_logger::init();
 
+    let scene_path = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_SCENE.to_string());
+    let scene = match Scene::load(&scene_path) {
+        Ok(scene) => scene,
+        Err(error) => {
+            eprintln!("{}: {}", scene_path, error);
+            std::process::exit(1);
+        }
+    };
+
     let event_loop = EventLoop::new().unwrap();
     let window = 
         WindowBuilder::new()
@@ -34,48 +45,35 @@ fn main() {
             .unwrap();
         
     let mut renderer = BaseRenderer::new(&window).block_on();
-    let entity_list = &mut renderer.entities;
-
-    let mut circles = Vec::new();
-
-    circles.push(
-        Circle::new(
-            entity_list,
-            1, 
-            0.02, 
-            Vector::new(0.0, 0.5), 
-            Vector::new(-0.0002, 0.0), 
-            Vector::new(0.0, 0.0)
-        )
-    );
-
-    circles.push(
-        Circle::new(
-            entity_list,
-            1, 
-            0.02, 
-            Vector::new(-0.4330127019, -0.25), 
-            Vector::new(0.0001, -0.00017320508076), 
-            Vector::new(0.0, 0.0)
-        )
-    );
+    renderer.set_timestep(scene.timestep);
+    renderer.set_max_substeps(scene.max_substeps);
 
-    circles.push(
-        Circle::new(
-            entity_list, 
-            1, 
-            0.02, 
-            Vector::new(0.4330127019, -0.25), 
-            Vector::new(0.0001, 0.0001732), 
-            Vector::new(0.0, 0.0)
-        )
-    );
+    let mut circles = scene.spawn(renderer.entities());
+    let mut integrator = scene.integrator();
+    let mut solver = scene.solver();
+    let collisions = scene.collisions;
+    // Left click grabs and flings bodies, or spawns new ones on empty space
+    let mut interaction = Interaction::new(scene.spawn, scene.trails);
 
-    let func = |el: &mut EntityList| {
-        // Apply gravity on each circle
-        Circle::gravity(&mut circles);
-        // Update each circle's data
-        Circle::update(&mut circles, el);
+    let func = |el: &mut EntityList, input: &InputState, time: FrameTime| {
+        // [ and ] halve and double the mass of spawned bodies
+        if input.key_pressed(KeyCode::BracketLeft) {
+            interaction.set_spawn_mass(interaction.spawn.mass / 2);
+        }
+        if input.key_pressed(KeyCode::BracketRight) {
+            interaction.set_spawn_mass(interaction.spawn.mass * 2);
+        }
+        interaction.update(&mut circles, el, input);
+        for _ in 0..time.steps {
+            // Apply gravity on each circle and update each circle's data
+            Circle::step(&mut circles, el, integrator.as_mut(), solver.as_mut(), time.dt);
+            interaction.hold(&mut circles, el, time.dt);
+            Circle::collide(&mut circles, el, collisions);
+        }
+        // Smooth out frames that fall between steps
+        Circle::interpolate(&mut circles, el, time.alpha);
+        // For the camera to follow, when asked to with C
+        el.camera_mut().set_center_of_mass(Circle::center_of_mass(&circles));
     };
 
     renderer.set_main_loop(func);
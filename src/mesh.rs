@@ -0,0 +1,153 @@
+// Mesh files hold a triangle list as text, vertices first and then the indices after a `~` line:
+//
+//     # Comments run to the end of the line, blank lines are skipped
+//     -0.5,-0.5 1,0,0
+//     0.5,-0.5 0,1,0
+//     0,0.5,0 0,0,1,0.5
+//     ~
+//     0 1 2
+//
+// Vertices are a position of 2 or 3 numbers, the third being ignored, optionally followed by an RGB or RGBA color,
+// white when left out. Without a `~` the vertices are taken three at a time
+
+use std::{
+    fmt,
+    path::Path
+};
+
+use crate::{
+    utils::defaults::Index,
+    vertex::{ Vertex, VertexData }
+};
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(std::io::Error),
+    // Lines and columns start at 1, columns count characters rather than bytes
+    Parse { line: usize, column: usize, message: String },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(error) => write!(f, "Failed to read mesh: {}", error),
+            MeshError::Parse { line, column, message } => write!(f, "Line {}, column {}: {}", line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl From<std::io::Error> for MeshError {
+    fn from(error: std::io::Error) -> Self {
+        MeshError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    // Triangle list
+    pub indices: Vec<Index>,
+}
+
+impl Mesh {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MeshError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, MeshError> {
+        let mut mesh = Mesh::default();
+        let mut in_indices = false;
+        // Just past the last index, for pointing at the end of an incomplete triangle
+        let mut last_index = (0, 0);
+
+        for (number, line) in source.lines().enumerate() {
+            let line_number = number + 1;
+            let line = line.split('#').next().unwrap();
+            // Columns are found as byte offsets into the line
+            let column = |offset: usize| line[..offset].chars().count() + 1;
+            let error = |offset: usize, message: String| MeshError::Parse { line: line_number, column: column(offset), message };
+
+            let mut words = words(line).peekable();
+            let Some(&(first_offset, first)) = words.peek() else { continue; };
+
+            if first == "~" {
+                if in_indices {
+                    return Err(error(first_offset, "Only one `~` is allowed".to_string()));
+                }
+                if let Some((offset, _)) = words.nth(1) {
+                    return Err(error(offset, "Expected nothing else on the `~` line".to_string()));
+                }
+                in_indices = true;
+                last_index = (line_number, column(first_offset + first.len()));
+                continue;
+            }
+
+            if in_indices {
+                for (offset, word) in words {
+                    let index: Index = word.parse().map_err(|_| error(offset, format!("`{}` is not an index", word)))?;
+                    if index as usize >= mesh.vertices.len() {
+                        return Err(error(offset, format!("Index {} is out of range for {} vertices", index, mesh.vertices.len())));
+                    }
+                    mesh.indices.push(index);
+                    last_index = (line_number, column(offset + word.len()));
+                }
+                continue;
+            }
+
+            let (_, position) = words.next().unwrap();
+            let position: [f32; 3] = parse_components(position, 2, 0.0).map_err(|(offset, message)| error(first_offset + offset, message))?;
+            let color = match words.next() {
+                Some((start, color)) => parse_components(color, 3, 1.0).map_err(|(offset, message)| error(start + offset, message))?,
+                None => [1.0; 4],
+            };
+            if let Some((offset, word)) = words.next() {
+                return Err(error(offset, format!("Unexpected `{}` after the color", word)));
+            }
+            mesh.vertices.push(Vertex { position: [position[0], position[1]], color });
+        }
+
+        if !in_indices {
+            if mesh.vertices.len() % 3 != 0 {
+                let line = source.lines().count().max(1);
+                return Err(MeshError::Parse { line, column: 1, message: format!("{} vertices don't make whole triangles", mesh.vertices.len()) });
+            }
+            mesh.indices = (0..mesh.vertices.len() as Index).collect();
+        } else if mesh.indices.len() % 3 != 0 {
+            let (line, column) = last_index;
+            return Err(MeshError::Parse { line, column, message: format!("{} indices don't make whole triangles", mesh.indices.len()) });
+        }
+
+        Ok(mesh)
+    }
+
+    pub fn vertex_data(&self) -> VertexData {
+        Vertex::to_vertex_data(&self.vertices)
+    }
+}
+
+// Whitespace separated words with the byte offset they start at
+fn words(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace().map(move |word| (word.as_ptr() as usize - line.as_ptr() as usize, word))
+}
+
+// Comma separated numbers, between `required` and N of them, the rest filled with `fill`.
+// Errors come with the byte offset into `text` they point at
+fn parse_components<const N: usize>(text: &str, required: usize, fill: f32) -> Result<[f32; N], (usize, String)> {
+    let mut components = [fill; N];
+    let mut count = 0;
+    let mut offset = 0;
+    for part in text.split(',') {
+        if count == N {
+            return Err((offset, format!("Expected at most {} numbers in `{}`", N, text)));
+        }
+        components[count] = part.parse().map_err(|_| (offset, format!("`{}` is not a number", part)))?;
+        count += 1;
+        offset += part.len() + 1;
+    }
+    if count < required {
+        return Err((0, format!("Expected at least {} numbers in `{}`", required, text)));
+    }
+    Ok(components)
+}
@@ -0,0 +1,72 @@
+struct Body {
+    color: vec4<f32>,
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    mass: f32,
+    radius: f32,
+}
+
+struct Params {
+    dt: f32,
+    g: f32,
+    softening: f32,
+    count: u32,
+    // 0 when unset
+    max_force: f32,
+    cutoff: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(0) @binding(1)
+var<storage, read_write> bodies: array<Body>;
+
+@group(0) @binding(2)
+var<storage, read_write> accelerations: array<vec2<f32>>;
+
+// Every body's pull on every other, O(n²) but in parallel
+@compute @workgroup_size(64)
+fn accelerate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+
+    let position = bodies[i].position;
+    let mass = bodies[i].mass;
+    var acceleration = vec2(0.0, 0.0);
+    for (var j = 0u; j < params.count; j += 1u) {
+        if (j == i) {
+            continue;
+        }
+        let pos_diff = bodies[j].position - position;
+        let distance_sq = dot(pos_diff, pos_diff);
+        if (params.cutoff > 0.0 && distance_sq > params.cutoff * params.cutoff) {
+            continue;
+        }
+        // Plummer softening
+        let softened_sq = distance_sq + params.softening * params.softening;
+        var acceleration_mag = params.g * bodies[j].mass / (softened_sq * sqrt(softened_sq));
+        if (params.max_force > 0.0) {
+            let force = acceleration_mag * sqrt(distance_sq) * mass;
+            if (force > params.max_force) {
+                acceleration_mag *= params.max_force / force;
+            }
+        }
+        acceleration += pos_diff * acceleration_mag;
+    }
+    accelerations[i] = acceleration;
+}
+
+// Symplectic euler, run once every acceleration of the step is known
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+
+    bodies[i].velocity += accelerations[i] * params.dt;
+    bodies[i].position += bodies[i].velocity * params.dt;
+}
@@ -0,0 +1,197 @@
+// Paths are built from lines, curves and arcs, flattened into contours of points as they go.
+// Filling one treats contours inside an odd number of others as holes, so a contour drawn inside
+// another cuts it out, whichever way either is wound. Contours are expected not to cross each other or themselves
+
+use crate::{
+    triangulate::triangulate_with_holes,
+    utils::{
+        defaults::{ Float, Index },
+        Vector
+    }
+};
+
+/// Furthest a flattened curve strays from the real one by default, in world units
+pub const DEFAULT_TOLERANCE: Float = 0.001;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    tolerance: Float,
+    contours: Vec<Vec<Vector<Float>>>,
+    // Whether the last contour was closed, in which case the next segment starts a new one
+    closed: bool,
+    current: Vector<Float>,
+}
+
+impl Default for Path {
+    fn default() -> Self { Self::new() }
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::with_tolerance(DEFAULT_TOLERANCE)
+    }
+
+    /// Curves added later are split into as few lines as keep within `tolerance` of them
+    pub fn with_tolerance(tolerance: Float) -> Self {
+        assert!(tolerance > 0.0, "Tolerance must be positive, got {}", tolerance);
+        Self { tolerance, contours: Vec::new(), closed: true, current: Vector::new(0.0, 0.0) }
+    }
+
+    pub fn tolerance(&self) -> Float { self.tolerance }
+
+    /// Flattened contours as built, any segment back to the start included
+    pub fn contours(&self) -> &[Vec<Vector<Float>>] { &self.contours }
+
+    /// Where the next segment starts
+    pub fn current(&self) -> Vector<Float> { self.current }
+
+    /// Starts a new contour at `point`
+    pub fn move_to(mut self, point: Vector<Float>) -> Self {
+        self.contours.push(vec![point]);
+        self.closed = false;
+        self.current = point;
+        self
+    }
+
+    pub fn line_to(mut self, point: Vector<Float>) -> Self {
+        self.push(point);
+        self
+    }
+
+    /// Quadratic Bézier curve from the current point, bending towards `control`
+    pub fn quad_to(mut self, control: Vector<Float>, end: Vector<Float>) -> Self {
+        let start = self.current;
+        // The curve's second derivative is constant, twice this
+        let bend = Vector::new(start.x() - 2.0 * control.x() + end.x(), start.y() - 2.0 * control.y() + end.y());
+        let segments = self.segments(bend.mag().sqrt() / 4.0);
+        for i in 1..=segments {
+            let t = i as Float / segments as Float;
+            let u = 1.0 - t;
+            self.push(Vector::new(
+                u * u * start.x() + 2.0 * u * t * control.x() + t * t * end.x(),
+                u * u * start.y() + 2.0 * u * t * control.y() + t * t * end.y(),
+            ));
+        }
+        self
+    }
+
+    /// Cubic Bézier curve from the current point, leaving towards `control1` and arriving from `control2`
+    pub fn cubic_to(mut self, control1: Vector<Float>, control2: Vector<Float>, end: Vector<Float>) -> Self {
+        let start = self.current;
+        // The second derivative is at most six times the larger of these
+        let bend1 = Vector::new(start.x() - 2.0 * control1.x() + control2.x(), start.y() - 2.0 * control1.y() + control2.y());
+        let bend2 = Vector::new(control1.x() - 2.0 * control2.x() + end.x(), control1.y() - 2.0 * control2.y() + end.y());
+        let segments = self.segments(bend1.mag().max(bend2.mag()).sqrt() * 0.75);
+        for i in 1..=segments {
+            let t = i as Float / segments as Float;
+            let u = 1.0 - t;
+            let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            self.push(Vector::new(
+                a * start.x() + b * control1.x() + c * control2.x() + d * end.x(),
+                a * start.y() + b * control1.y() + c * control2.y() + d * end.y(),
+            ));
+        }
+        self
+    }
+
+    /// Circular arc from the current point around `center`, turning by `sweep` radians, counter-clockwise when positive
+    pub fn arc_to(mut self, center: Vector<Float>, sweep: Float) -> Self {
+        let offset = Vector::vec_diff(self.current, center);
+        let radius = offset.mag().sqrt();
+        if radius == 0.0 || sweep == 0.0 { return self; }
+
+        // Each chord strays from the circle by radius * (1 - cos(angle / 2)) at its middle
+        let max_angle = 2.0 * (1.0 - (self.tolerance / radius).min(1.0)).acos();
+        let segments = (sweep.abs() / max_angle).ceil().max(1.0) as usize;
+        let start_angle = offset.y().atan2(offset.x());
+        for i in 1..=segments {
+            let angle = start_angle + sweep * i as Float / segments as Float;
+            self.push(Vector::new(center.x() + radius * angle.cos(), center.y() + radius * angle.sin()));
+        }
+        self
+    }
+
+    /// Ends the contour, going back to its start. Contours are filled as closed either way
+    pub fn close(mut self) -> Self {
+        if let (false, Some(contour)) = (self.closed, self.contours.last()) {
+            self.current = contour[0];
+        }
+        self.closed = true;
+        self
+    }
+
+    /// Vertices and a counter-clockwise triangle list filling the path, ready for `Entity::set_geometry`
+    pub fn fill(&self) -> (Vec<Vector<Float>>, Vec<Index>) {
+        let contours: Vec<Vec<Vector<Float>>> = self.contours.iter().map(|contour| clean(contour)).filter(|contour| contour.len() >= 3).collect();
+
+        // How many contours each one is inside of
+        let depths: Vec<usize> = contours
+            .iter()
+            .enumerate()
+            .map(|(i, contour)| {
+                contours.iter().enumerate().filter(|&(j, other)| i != j && contains(other, contour[0])).count()
+            })
+            .collect();
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (i, outline) in contours.iter().enumerate() {
+            if !depths[i].is_multiple_of(2) { continue; }
+            // Directly inside this outline, rather than inside a hole in it
+            let holes: Vec<&[Vector<Float>]> = contours
+                .iter()
+                .enumerate()
+                .filter(|&(j, hole)| depths[j] == depths[i] + 1 && contains(outline, hole[0]))
+                .map(|(_, hole)| hole.as_slice())
+                .collect();
+
+            let (shape_vertices, shape_indices) = triangulate_with_holes(outline, &holes);
+            let offset = vertices.len() as Index;
+            indices.extend(shape_indices.into_iter().map(|index| index + offset));
+            vertices.extend(shape_vertices);
+        }
+        (vertices, indices)
+    }
+
+    fn push(&mut self, point: Vector<Float>) {
+        if self.closed {
+            // Picks up where the last contour ended, like a move there
+            self.contours.push(vec![self.current]);
+            self.closed = false;
+        }
+        self.contours.last_mut().unwrap().push(point);
+        self.current = point;
+    }
+
+    // Lines needed to keep a curve whose chords stray by `deviation` / n² within the tolerance
+    fn segments(&self, deviation: Float) -> usize {
+        ((deviation / self.tolerance).sqrt().ceil() as usize).max(1)
+    }
+}
+
+// Without repeated points, including the last one when it's back at the start
+fn clean(contour: &[Vector<Float>]) -> Vec<Vector<Float>> {
+    let mut points: Vec<Vector<Float>> = Vec::with_capacity(contour.len());
+    for &point in contour {
+        if points.last() != Some(&point) {
+            points.push(point);
+        }
+    }
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    points
+}
+
+// Even-odd test of `point` against the polygon
+fn contains(polygon: &[Vector<Float>], point: Vector<Float>) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.y() > point.y()) != (b.y() > point.y())
+            && point.x() < a.x() + (point.y() - a.y()) / (b.y() - a.y()) * (b.x() - a.x()) {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -0,0 +1,118 @@
+use std::{
+    io::{ self, Write },
+    path::PathBuf,
+    process::{ Child, Command, Stdio }
+};
+
+use crate::image::{ self, ImageFormat };
+
+/// What to do with recorded frames. Every `every_nth_frame`th rendered frame is written to
+/// `directory` as a numbered image sequence and / or piped as raw RGBA8 to the stdin of `encoder`
+pub struct Recording {
+    pub directory: Option<PathBuf>,
+    pub format: ImageFormat,
+    pub encoder: Option<Command>,
+    pub every_nth_frame: u32,
+}
+
+impl Recording {
+    pub fn image_sequence(directory: impl Into<PathBuf>, format: ImageFormat) -> Self {
+        Self { directory: Some(directory.into()), format, encoder: None, every_nth_frame: 1 }
+    }
+
+    pub fn encoder(command: Command) -> Self {
+        Self { directory: None, format: ImageFormat::Png, encoder: Some(command), every_nth_frame: 1 }
+    }
+
+    pub fn every_nth_frame(mut self, n: u32) -> Self {
+        self.every_nth_frame = n.max(1);
+        self
+    }
+
+    /// An ffmpeg invocation reading raw frames of the given size from stdin and encoding them into `output`
+    pub fn ffmpeg_command(width: u32, height: u32, framerate: u32, output: impl Into<PathBuf>) -> Command {
+        let mut command = Command::new("ffmpeg");
+        command
+            .args(["-y", "-f", "rawvideo", "-pixel_format", "rgba"])
+            .args(["-video_size", &format!("{}x{}", width, height)])
+            .args(["-framerate", &framerate.to_string()])
+            .args(["-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(output.into());
+        command
+    }
+}
+
+/// A recording in progress, driven by `BaseRenderer` once started with `start_recording`,
+/// or by hand with frames captured some other way
+pub struct Recorder {
+    directory: Option<PathBuf>,
+    format: ImageFormat,
+    encoder: Option<Child>,
+    every_nth_frame: u32,
+    // Frames rendered since the recording started
+    frame: u64,
+    // Frames actually written out
+    written: u64,
+}
+
+impl Recorder {
+    pub fn start(recording: Recording) -> io::Result<Self> {
+        if let Some(directory) = &recording.directory {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        let encoder = match recording.encoder {
+            Some(mut command) => Some(command.stdin(Stdio::piped()).spawn()?),
+            None => None,
+        };
+
+        Ok(Self {
+            directory: recording.directory,
+            format: recording.format,
+            encoder,
+            every_nth_frame: recording.every_nth_frame.max(1),
+            frame: 0,
+            written: 0,
+        })
+    }
+
+    /// Whether the frame about to be recorded will be written out
+    pub fn wants_frame(&self) -> bool {
+        self.frame.is_multiple_of(self.every_nth_frame as u64)
+    }
+
+    /// Advances the frame counter, writing `rgba` out if this frame is wanted
+    pub fn record(&mut self, width: u32, height: u32, rgba: Option<&[u8]>) -> io::Result<()> {
+        let wanted = self.wants_frame();
+        self.frame += 1;
+
+        let rgba = match rgba {
+            Some(rgba) if wanted => rgba,
+            _ => return Ok(()),
+        };
+
+        if let Some(directory) = &self.directory {
+            let path = directory.join(format!("frame-{:06}.{}", self.written, self.format.extension()));
+            image::save_image(path, self.format, width, height, rgba)?;
+        }
+
+        if let Some(stdin) = self.encoder.as_mut().and_then(|encoder| encoder.stdin.as_mut()) {
+            stdin.write_all(rgba)?;
+        }
+
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Closes the encoder's stdin and waits for it to finish. Returns the amount of frames written
+    pub fn finish(mut self) -> io::Result<u64> {
+        if let Some(mut encoder) = self.encoder.take() {
+            drop(encoder.stdin.take());
+            let status = encoder.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("Encoder exited with {}", status)));
+            }
+        }
+        Ok(self.written)
+    }
+}
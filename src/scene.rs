@@ -0,0 +1,307 @@
+// Scene files describe a simulation's initial conditions, one setting or body per line:
+//
+//     # Comments run to the end of the line
+//     g 0.00036
+//     softening 0.01
+//     timestep 0.0166667
+//     max_substeps 8
+//     integrator velocity_verlet
+//     solver barnes_hut 0.5
+//     collisions elastic 0.9
+//     trails 120 0.004
+//     spawn mass=1 radius=0.02 color=1,1,1
+//     body mass=1 radius=0.02 position=0,0.5 velocity=-0.012,0 color=0,0.5,0.5
+//
+// Every setting is optional. Bodies take `shader=base` or `shader=color`, a color implying the latter.
+// `trails` takes the number of steps to keep and optionally a width, drawing thin lines without one.
+// `spawn` describes the bodies added by clicking on empty space, any position or velocity given is ignored
+
+use std::{
+    fmt,
+    path::Path
+};
+
+use crate::{
+    circle::{ Circle, CircleShader },
+    collision::CollisionResponse,
+    entity::EntityList,
+    gravity::{ BarnesHut, BruteForce, GravityConfig, GravitySolver },
+    integrator::{ self, Integrator },
+    trail::TrailStyle,
+    utils::{
+        defaults::Float,
+        Vector
+    }
+};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    // Line numbers start at 1
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "Failed to read scene: {}", error),
+            SceneError::Parse { line, message } => write!(f, "Line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(error: std::io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolverKind {
+    BruteForce,
+    // With its opening angle
+    BarnesHut(Float),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyDescription {
+    pub mass: usize,
+    pub radius: Float,
+    pub position: [Float; 2],
+    pub velocity: [Float; 2],
+    pub shader: CircleShader,
+}
+
+impl BodyDescription {
+    /// Adds a circle for the body to `entity_list`, with a trail in its color if given a style
+    pub fn spawn(&self, entity_list: &mut EntityList, trail: Option<TrailStyle>) -> Circle {
+        let mut circle = Circle::with_shader(
+            entity_list,
+            self.mass,
+            self.radius,
+            Vector { pos: self.position },
+            Vector { pos: self.velocity },
+            self.shader,
+        );
+        if let Some(mut style) = trail {
+            if let CircleShader::Color([r, g, b]) = self.shader {
+                style.head_color = [r, g, b, style.head_color[3]];
+                style.tail_color = [r, g, b, style.tail_color[3]];
+            }
+            circle.set_trail(entity_list, style);
+        }
+        circle
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub gravity: GravityConfig,
+    pub timestep: Float,
+    pub max_substeps: u32,
+    // As accepted by `integrator::by_name`
+    pub integrator: String,
+    pub solver: SolverKind,
+    pub collisions: CollisionResponse,
+    // Drawn behind every body when set, in the body's color if it has one
+    pub trails: Option<TrailStyle>,
+    // Template for bodies added with the mouse
+    pub spawn: BodyDescription,
+    pub bodies: Vec<BodyDescription>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            gravity: GravityConfig::default(),
+            timestep: 1.0 / 60.0,
+            max_substeps: 8,
+            integrator: "velocity_verlet".to_string(),
+            solver: SolverKind::BruteForce,
+            collisions: CollisionResponse::Ignore,
+            trails: None,
+            spawn: BodyDescription {
+                mass: 1,
+                radius: 0.02,
+                position: [0.0; 2],
+                velocity: [0.0; 2],
+                shader: CircleShader::Base,
+            },
+            bodies: Vec::new(),
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, SceneError> {
+        let mut scene = Scene::default();
+
+        for (number, line) in source.lines().enumerate() {
+            let line_number = number + 1;
+            let error = |message: String| SceneError::Parse { line: line_number, message };
+
+            let line = line.split('#').next().unwrap().trim();
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else { continue; };
+            let arguments: Vec<&str> = words.collect();
+
+            // Settings take exactly one argument, apart from the ones handled first
+            let single = || match arguments[..] {
+                [argument] => Ok(argument),
+                _ => Err(error(format!("`{}` takes one argument", keyword))),
+            };
+
+            match keyword {
+                "body" => scene.bodies.push(parse_body(&arguments).map_err(error)?),
+                "spawn" => scene.spawn = parse_body(&arguments).map_err(error)?,
+                "solver" => scene.solver = match arguments[..] {
+                    ["brute_force"] => SolverKind::BruteForce,
+                    ["barnes_hut"] => SolverKind::BarnesHut(0.5),
+                    ["barnes_hut", theta] => {
+                        let theta = parse_number(theta).map_err(error)?;
+                        if !theta.is_finite() || theta < 0.0 {
+                            return Err(error("The opening angle has to be a finite number, zero or above".to_string()));
+                        }
+                        SolverKind::BarnesHut(theta)
+                    },
+                    _ => return Err(error("Expected `brute_force` or `barnes_hut [theta]`".to_string())),
+                },
+                "collisions" => scene.collisions = match arguments[..] {
+                    ["ignore"] => CollisionResponse::Ignore,
+                    ["merge"] => CollisionResponse::Merge,
+                    ["elastic"] => CollisionResponse::Elastic { restitution: 1.0 },
+                    ["elastic", restitution] => CollisionResponse::Elastic { restitution: parse_number(restitution).map_err(error)? },
+                    _ => return Err(error("Expected `ignore`, `merge` or `elastic [restitution]`".to_string())),
+                },
+                "trails" => {
+                    let (length, width) = match arguments[..] {
+                        [length] => (length, 0.0),
+                        [length, width] => (length, parse_number(width).map_err(error)?),
+                        _ => return Err(error("Expected `trails length [width]`".to_string())),
+                    };
+                    let length = length.parse().map_err(|_| error(format!("`{}` is not a whole number", length)))?;
+                    if width < 0.0 {
+                        return Err(error("The trail width can't be negative".to_string()));
+                    }
+                    scene.trails = Some(TrailStyle { length, width, ..TrailStyle::default() });
+                },
+                "g" => scene.gravity.g = parse_number(single()?).map_err(error)?,
+                "softening" => scene.gravity.softening = parse_number(single()?).map_err(error)?,
+                "max_force" => scene.gravity.max_force = Some(parse_number(single()?).map_err(error)?),
+                "cutoff" => scene.gravity.cutoff = Some(parse_number(single()?).map_err(error)?),
+                "timestep" => {
+                    scene.timestep = parse_number(single()?).map_err(error)?;
+                    if !scene.timestep.is_finite() || scene.timestep <= 0.0 {
+                        return Err(error("The timestep has to be a positive number".to_string()));
+                    }
+                },
+                "max_substeps" => {
+                    let argument = single()?;
+                    scene.max_substeps = argument.parse().map_err(|_| error(format!("`{}` is not a whole number", argument)))?;
+                },
+                "integrator" => {
+                    let name = single()?;
+                    if integrator::by_name(name).is_none() {
+                        return Err(error(format!("Unknown integrator `{}`", name)));
+                    }
+                    scene.integrator = name.to_string();
+                },
+                _ => return Err(error(format!("Unknown setting `{}`", keyword))),
+            }
+        }
+
+        Ok(scene)
+    }
+
+    pub fn integrator(&self) -> Box<dyn Integrator> {
+        // Checked while parsing, but the field could have been changed since
+        integrator::by_name(&self.integrator).unwrap_or_else(|| panic!("Unknown integrator `{}`", self.integrator))
+    }
+
+    pub fn solver(&self) -> Box<dyn GravitySolver> {
+        let mut solver: Box<dyn GravitySolver> = match self.solver {
+            SolverKind::BruteForce => Box::new(BruteForce::default()),
+            SolverKind::BarnesHut(theta) => Box::new(BarnesHut::new(theta)),
+        };
+        solver.set_config(self.gravity);
+        solver
+    }
+
+    /// Adds a circle for every body to `entity_list`
+    pub fn spawn(&self, entity_list: &mut EntityList) -> Vec<Circle> {
+        self.bodies
+            .iter()
+            .map(|body| body.spawn(entity_list, self.trails))
+            .collect()
+    }
+}
+
+fn parse_number(text: &str) -> Result<Float, String> {
+    text.parse().map_err(|_| format!("`{}` is not a number", text))
+}
+
+// Comma separated, without spaces
+fn parse_numbers<const N: usize>(text: &str) -> Result<[Float; N], String> {
+    let mut numbers = [0.0; N];
+    let mut parts = text.split(',');
+    for number in &mut numbers {
+        *number = parse_number(parts.next().ok_or_else(|| format!("`{}` should have {} components", text, N))?)?;
+    }
+    if parts.next().is_some() {
+        return Err(format!("`{}` should have {} components", text, N));
+    }
+    Ok(numbers)
+}
+
+fn parse_body(arguments: &[&str]) -> Result<BodyDescription, String> {
+    let mut mass = None;
+    let mut radius = None;
+    let mut position = [0.0; 2];
+    let mut velocity = [0.0; 2];
+    let mut color = None;
+    let mut shader = None;
+
+    for argument in arguments {
+        let (key, value) = argument.split_once('=').ok_or_else(|| format!("Expected `key=value`, found `{}`", argument))?;
+        match key {
+            "mass" => mass = Some(value.parse().map_err(|_| format!("`{}` is not a whole number", value))?),
+            "radius" => radius = Some(parse_number(value)?),
+            "position" => position = parse_numbers(value)?,
+            "velocity" => velocity = parse_numbers(value)?,
+            "color" => color = Some(parse_numbers(value)?),
+            "shader" => shader = Some(value),
+            _ => return Err(format!("Unknown body property `{}`", key)),
+        }
+    }
+
+    let shader = match (shader, color) {
+        (None | Some("color"), Some(color)) => CircleShader::Color(color),
+        (None | Some("base"), None) => CircleShader::Base,
+        (Some("base"), Some(_)) => return Err("The base shader has no color".to_string()),
+        (Some("color"), None) => return Err("The color shader needs a `color`".to_string()),
+        (Some(other), _) => return Err(format!("Unknown shader `{}`", other)),
+    };
+
+    let mass = mass.ok_or("Bodies need a `mass`")?;
+    let radius = radius.ok_or("Bodies need a `radius`")?;
+    if mass == 0 {
+        return Err("The mass has to be positive".to_string());
+    }
+    if !radius.is_finite() || radius <= 0.0 {
+        return Err("The radius has to be a positive number".to_string());
+    }
+
+    Ok(BodyDescription {
+        mass,
+        radius,
+        position,
+        velocity,
+        shader,
+    })
+}
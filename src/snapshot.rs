@@ -0,0 +1,220 @@
+// Snapshot files are little endian binary:
+//
+//     magic            b"NBODYSNP"
+//     version          u32
+//     steps            u64
+//     time             f64
+//     dt               f32
+//     g, softening     f32 f32
+//     max_force        u8 flag, f32
+//     cutoff           u8 flag, f32
+//     circle count     u32
+//     per circle       mass u64, radius f32, position, previous position, velocity and acceleration as f32 pairs,
+//                      shader u8 (0 base, 1 color) followed by 3 f32 of color
+//
+// Floats are stored by their bits, so a restored simulation carries on exactly where it stopped.
+// Only the bodies and gravity are stored. Trails, the integrator, the gravity solver and the collision response
+// are not: restored circles start without trails, and the rest has to be set up again, e.g. from the same scene
+
+use std::{
+    fmt,
+    fs::File,
+    io::{ self, BufReader, BufWriter, Read, Write },
+    path::Path
+};
+
+use crate::{
+    circle::{ Circle, CircleShader, CircleState },
+    entity::EntityList,
+    gravity::GravityConfig,
+    utils::{
+        defaults::Float,
+        Vector
+    }
+};
+
+const MAGIC: &[u8; 8] = b"NBODYSNP";
+pub const VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    NotASnapshot,
+    UnsupportedVersion(u32),
+    Corrupt(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "Failed to read snapshot: {}", error),
+            SnapshotError::NotASnapshot => write!(f, "Not a snapshot file"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "Snapshot version {} is not supported, {} is", version, VERSION),
+            SnapshotError::Corrupt(message) => write!(f, "Corrupt snapshot: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => SnapshotError::Corrupt("File ends early".to_string()),
+            _ => SnapshotError::Io(error),
+        }
+    }
+}
+
+/// The state of a simulation of circles, without their trails. Integrators, solvers and collision responses are
+/// not included, so resuming with different ones, or with `RungeKutta45` whose substep size is carried between steps,
+/// won't follow the same trajectory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    // Steps taken so far
+    pub steps: u64,
+    // Simulated seconds
+    pub time: f64,
+    pub dt: Float,
+    pub gravity: GravityConfig,
+    pub circles: Vec<CircleState>,
+}
+
+impl Snapshot {
+    pub fn capture(circles: &[Circle], steps: u64, time: f64, dt: Float, gravity: GravityConfig) -> Self {
+        Self { steps, time, dt, gravity, circles: circles.iter().map(Circle::state).collect() }
+    }
+
+    /// Adds the circles to a fresh `entity_list`, without trails
+    pub fn restore(&self, entity_list: &mut EntityList) -> Vec<Circle> {
+        self.circles.iter().map(|state| Circle::from_state(entity_list, state)).collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&self.steps.to_le_bytes())?;
+        writer.write_all(&self.time.to_le_bytes())?;
+        write_float(&mut writer, self.dt)?;
+        write_float(&mut writer, self.gravity.g)?;
+        write_float(&mut writer, self.gravity.softening)?;
+        write_optional(&mut writer, self.gravity.max_force)?;
+        write_optional(&mut writer, self.gravity.cutoff)?;
+
+        writer.write_all(&(self.circles.len() as u32).to_le_bytes())?;
+        for circle in &self.circles {
+            writer.write_all(&(circle.mass as u64).to_le_bytes())?;
+            write_float(&mut writer, circle.radius)?;
+            for vector in [circle.position, circle.previous_position, circle.velocity, circle.acceleration] {
+                write_float(&mut writer, vector.x())?;
+                write_float(&mut writer, vector.y())?;
+            }
+            let (tag, color) = match circle.shader {
+                CircleShader::Base => (0u8, [0.0; 3]),
+                CircleShader::Color(color) => (1u8, color),
+            };
+            writer.write_all(&[tag])?;
+            for channel in color {
+                write_float(&mut writer, channel)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read(mut reader: impl Read) -> Result<Self, SnapshotError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic).map_err(|_| SnapshotError::NotASnapshot)?;
+        if &magic != MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+
+        let version = u32::from_le_bytes(read_bytes(&mut reader)?);
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let steps = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let time = f64::from_le_bytes(read_bytes(&mut reader)?);
+        let dt = read_float(&mut reader)?;
+        let gravity = GravityConfig {
+            g: read_float(&mut reader)?,
+            softening: read_float(&mut reader)?,
+            max_force: read_optional(&mut reader)?,
+            cutoff: read_optional(&mut reader)?,
+        };
+
+        let count = u32::from_le_bytes(read_bytes(&mut reader)?);
+        // Not trusting the count for the allocation, a corrupt one would run out of data soon enough anyway
+        let mut circles = Vec::with_capacity((count as usize).min(1 << 16));
+        for _ in 0..count {
+            let mass = u64::from_le_bytes(read_bytes(&mut reader)?) as usize;
+            let radius = read_float(&mut reader)?;
+            let mut vectors = [Vector::new(0.0, 0.0); 4];
+            for vector in &mut vectors {
+                *vector = Vector::new(read_float(&mut reader)?, read_float(&mut reader)?);
+            }
+            let [tag] = read_bytes(&mut reader)?;
+            let color = [read_float(&mut reader)?, read_float(&mut reader)?, read_float(&mut reader)?];
+            let shader = match tag {
+                0 => CircleShader::Base,
+                1 => CircleShader::Color(color),
+                _ => return Err(SnapshotError::Corrupt(format!("Unknown shader {}", tag))),
+            };
+
+            circles.push(CircleState {
+                mass,
+                radius,
+                position: vectors[0],
+                previous_position: vectors[1],
+                velocity: vectors[2],
+                acceleration: vectors[3],
+                shader,
+            });
+        }
+
+        if reader.read(&mut [0])? != 0 {
+            return Err(SnapshotError::Corrupt("Data after the last circle".to_string()));
+        }
+
+        Ok(Self { steps, time, dt, gravity, circles })
+    }
+}
+
+fn write_float(writer: &mut impl Write, value: Float) -> io::Result<()> {
+    writer.write_all(&value.to_bits().to_le_bytes())
+}
+
+fn write_optional(writer: &mut impl Write, value: Option<Float>) -> io::Result<()> {
+    writer.write_all(&[value.is_some() as u8])?;
+    write_float(writer, value.unwrap_or(0.0))
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_float(reader: &mut impl Read) -> io::Result<Float> {
+    Ok(Float::from_bits(u32::from_le_bytes(read_bytes(reader)?)))
+}
+
+fn read_optional(reader: &mut impl Read) -> Result<Option<Float>, SnapshotError> {
+    let [flag] = read_bytes(reader)?;
+    let value = read_float(reader)?;
+    match flag {
+        0 => Ok(None),
+        1 => Ok(Some(value)),
+        _ => Err(SnapshotError::Corrupt(format!("Invalid flag {}", flag))),
+    }
+}
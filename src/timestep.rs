@@ -0,0 +1,166 @@
+use crate::utils::defaults::Float;
+
+/// Timing of a rendered frame, handed to the main loop.
+/// The simulation should be advanced `steps` times by `dt`, after which `alpha` is how far along
+/// the next step the frame is drawn, for interpolating between the previous and the current state
+#[derive(Debug, Clone, Copy)]
+pub struct FrameTime {
+    pub dt: Float,
+    pub steps: u32,
+    pub alpha: Float,
+    // Real seconds since the last frame
+    pub frame_time: Float,
+    // Simulated seconds once the steps have run
+    pub time: f64,
+}
+
+/// Accumulates real frame times into a whole number of fixed steps
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    dt: Float,
+    // Backlogs past this many steps in a frame are dropped instead of making the next frames longer
+    max_substeps: u32,
+    accumulator: Float,
+    time: f64,
+    paused: bool,
+    // Taken on the next frame even while paused
+    queued_steps: u32,
+    // Simulated seconds per real second
+    time_scale: Float,
+    // Fraction of a step left over by `advance_steps` at a time scale that is not a whole number
+    step_carry: Float,
+}
+
+impl FixedTimestep {
+    pub const MIN_TIME_SCALE: Float = 1.0 / 64.0;
+    pub const MAX_TIME_SCALE: Float = 64.0;
+
+    /// Panics unless `dt` is positive and finite
+    pub fn new(dt: Float, max_substeps: u32) -> Self {
+        assert!(Self::valid_dt(dt), "The timestep has to be positive and finite, got {}", dt);
+        Self {
+            dt,
+            max_substeps: max_substeps.max(1),
+            accumulator: 0.0,
+            time: 0.0,
+            paused: false,
+            queued_steps: 0,
+            time_scale: 1.0,
+            step_carry: 0.0,
+        }
+    }
+
+    pub fn dt(&self) -> Float { self.dt }
+
+    /// A `dt` that isn't positive and finite is ignored, keeping the current one
+    pub fn set_dt(&mut self, dt: Float) {
+        if Self::valid_dt(dt) {
+            self.dt = dt;
+        }
+    }
+
+    pub fn max_substeps(&self) -> u32 { self.max_substeps }
+
+    pub fn set_max_substeps(&mut self, max_substeps: u32) { self.max_substeps = max_substeps.max(1); }
+
+    pub fn time(&self) -> f64 { self.time }
+
+    pub fn set_time(&mut self, time: f64) { self.time = time; }
+
+    pub fn paused(&self) -> bool { self.paused }
+
+    /// While paused frames take no steps apart from the ones queued with `step_once`, and the accumulator is kept as is
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.queued_steps = 0;
+        }
+    }
+
+    /// Pauses, and has the next frame take exactly one step
+    pub fn step_once(&mut self) {
+        self.paused = true;
+        self.queued_steps += 1;
+    }
+
+    pub fn time_scale(&self) -> Float { self.time_scale }
+
+    /// Above 1 frames take more steps, up to `max_substeps` times the scale rounded up, and below 1 fewer
+    pub fn set_time_scale(&mut self, time_scale: Float) {
+        // Clamping lets NaN through
+        if !time_scale.is_nan() {
+            self.time_scale = time_scale.clamp(Self::MIN_TIME_SCALE, Self::MAX_TIME_SCALE);
+        }
+    }
+
+    fn valid_dt(dt: Float) -> bool {
+        dt > 0.0 && dt.is_finite()
+    }
+
+    /// Adds `frame_time` seconds to the accumulator and takes as many whole steps out of it as allowed
+    pub fn advance(&mut self, frame_time: Float) -> FrameTime {
+        if self.paused {
+            return self.advance_paused(frame_time);
+        }
+
+        self.accumulator += frame_time * self.time_scale;
+
+        // Sped up frames are meant to take more steps, so the limit grows with the scale
+        let max_substeps = self.max_substeps * self.time_scale.ceil().max(1.0) as u32;
+        let mut steps = (self.accumulator / self.dt) as u32;
+        if steps > max_substeps {
+            steps = max_substeps;
+            // Only keep the fraction of a step, the rest of the backlog is lost
+            self.accumulator %= self.dt;
+        } else {
+            self.accumulator -= steps as Float * self.dt;
+        }
+
+        self.time += steps as f64 * self.dt as f64;
+
+        FrameTime {
+            dt: self.dt,
+            steps,
+            alpha: (self.accumulator / self.dt).clamp(0.0, 1.0),
+            frame_time,
+            time: self.time,
+        }
+    }
+
+    /// Takes exactly `steps` steps, times the time scale, regardless of real time, for deterministic output
+    pub fn advance_steps(&mut self, steps: u32) -> FrameTime {
+        if self.paused {
+            return self.advance_paused(steps as Float * self.dt);
+        }
+
+        self.step_carry += steps as Float * self.time_scale;
+        let steps = self.step_carry.floor();
+        self.step_carry -= steps;
+        let steps = steps as u32;
+
+        self.accumulator = 0.0;
+        self.time += steps as f64 * self.dt as f64;
+
+        FrameTime {
+            dt: self.dt,
+            steps,
+            alpha: 0.0,
+            frame_time: steps as Float * self.dt,
+            time: self.time,
+        }
+    }
+
+    // Only the queued steps, drawn where the last frame left off
+    fn advance_paused(&mut self, frame_time: Float) -> FrameTime {
+        let steps = std::mem::take(&mut self.queued_steps);
+        self.time += steps as f64 * self.dt as f64;
+
+        FrameTime {
+            dt: self.dt,
+            steps,
+            alpha: (self.accumulator / self.dt).clamp(0.0, 1.0),
+            frame_time,
+            time: self.time,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+
+use crate::{
+    entity::EntityList,
+    utils::{
+        self,
+        defaults::{ Float, Index },
+        Vector
+    }
+};
+
+/// Trails are drawn on this layer, behind everything on the default one
+pub const TRAIL_LAYER: i32 = -1;
+
+/// How a trail looks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailStyle {
+    // Most points kept
+    pub length: usize,
+    // In world units, a thin line strip at 0 and a ribbon otherwise
+    pub width: Float,
+    // Linear RGBA, faded from the newest point to the oldest
+    pub head_color: [f32; 4],
+    pub tail_color: [f32; 4],
+}
+
+impl Default for TrailStyle {
+    fn default() -> Self {
+        Self {
+            length: 120,
+            width: 0.0,
+            head_color: [1.0, 1.0, 1.0, 0.8],
+            tail_color: [1.0, 1.0, 1.0, 0.0],
+        }
+    }
+}
+
+// Shader arguments of a trail's entity
+struct TrailArgs {
+    head_color: [f32; 4],
+    tail_color: [f32; 4],
+    // Points in the trail, the newest one first
+    points: u32,
+    // 1 for lines, 2 for ribbons
+    vertices_per_point: u32,
+}
+
+impl TrailArgs {
+    // Laid out as `trail_shader.wgsl` reads them, padded to the 16 byte alignment of its vec4s
+    fn bytes(&self) -> [u8; 48] {
+        let mut bytes = [0; 48];
+        for (i, value) in self.head_color.iter().chain(&self.tail_color).enumerate() {
+            bytes[i * 4..i * 4 + 4].copy_from_slice(&value.to_ne_bytes());
+        }
+        bytes[32..36].copy_from_slice(&self.points.to_ne_bytes());
+        bytes[36..40].copy_from_slice(&self.vertices_per_point.to_ne_bytes());
+        bytes
+    }
+}
+
+/// The recent positions of something moving, kept in a ring buffer and drawn as a fading strip
+pub struct Trail {
+    index: usize,
+    style: TrailStyle,
+    // Newest first
+    points: VecDeque<Vector<Float>>,
+    // Reused between uploads
+    vertices: Vec<Vector<Float>>,
+}
+
+impl Trail {
+    /// Adds the entity drawing the trail to `entity_list`
+    pub fn new(entity_list: &mut EntityList, style: TrailStyle) -> Self {
+        let style = TrailStyle { length: style.length.max(2), ..style };
+        let vertices_per_point = Self::vertices_per_point(&style);
+        // Room for an extra head point, see `upload`
+        let capacity = (style.length + 1) * vertices_per_point;
+
+        let topology = if vertices_per_point == 1 {
+            wgpu::PrimitiveTopology::LineStrip
+        } else {
+            wgpu::PrimitiveTopology::TriangleStrip
+        };
+
+        let device = entity_list.device.clone();
+        let format = entity_list.format();
+        let index = entity_list.next_index();
+        let entity = entity_list.add_entity();
+        entity.set_geometry(&vec![Vector::new(0.0, 0.0); capacity], &(0..capacity as Index).collect::<Vec<_>>());
+        entity.set_index_count(0);
+        entity.set_layer(TRAIL_LAYER);
+        entity.set_render_pipeline(
+            utils::generate_strip_render_pipeline(
+                &device,
+                format,
+                device.create_shader_module(wgpu::include_wgsl!("trail_shader.wgsl")),
+                topology
+            )
+        );
+
+        let mut trail = Self { index, style, points: VecDeque::with_capacity(style.length), vertices: Vec::with_capacity(capacity) };
+        entity_list.get_entity_unchecked(index).set_shader_args(trail.args(0).bytes());
+        trail.upload(entity_list, None);
+        trail
+    }
+
+    fn vertices_per_point(style: &TrailStyle) -> usize {
+        if style.width > 0.0 { 2 } else { 1 }
+    }
+
+    fn args(&self, points: usize) -> TrailArgs {
+        TrailArgs {
+            head_color: self.style.head_color,
+            tail_color: self.style.tail_color,
+            points: points as u32,
+            vertices_per_point: Self::vertices_per_point(&self.style) as u32,
+        }
+    }
+
+    /// Index of the entity drawing the trail
+    pub fn entity_index(&self) -> usize { self.index }
+
+    pub fn style(&self) -> &TrailStyle { &self.style }
+
+    /// Changes the colors from the next upload on, the length and width are fixed
+    pub fn set_colors(&mut self, head_color: [f32; 4], tail_color: [f32; 4]) {
+        self.style.head_color = head_color;
+        self.style.tail_color = tail_color;
+    }
+
+    /// Adds the newest point, dropping the oldest one once the trail is full
+    pub fn push(&mut self, point: Vector<Float>) {
+        if self.points.len() == self.style.length {
+            self.points.pop_back();
+        }
+        self.points.push_front(point);
+    }
+
+    pub fn clear(&mut self) { self.points.clear(); }
+
+    pub fn len(&self) -> usize { self.points.len() }
+
+    pub fn is_empty(&self) -> bool { self.points.is_empty() }
+
+    /// The points, newest first
+    pub fn points(&self) -> impl Iterator<Item = &Vector<Float>> { self.points.iter() }
+
+    /// Sends the trail to the GPU. `head` is drawn as an extra newest point without being kept,
+    /// e.g. where a body is drawn between two steps
+    pub fn upload(&mut self, entity_list: &mut EntityList, head: Option<Vector<Float>>) {
+        let points: Vec<Vector<Float>> = head.into_iter().chain(self.points.iter().copied()).collect();
+
+        self.vertices.clear();
+        if Self::vertices_per_point(&self.style) == 1 {
+            self.vertices.extend_from_slice(&points);
+        } else {
+            let half_width = self.style.width * 0.5;
+            let mut normal = Vector::new(0.0, half_width);
+            for i in 0..points.len() {
+                // Along the trail around this point, the ends only have one neighbour
+                let direction = Vector::vec_diff(points[i.saturating_sub(1)], points[(i + 1).min(points.len() - 1)]);
+                // Points on top of each other keep the last side
+                // `mag` is squared
+                let length = direction.mag().sqrt();
+                if length > 0.0 {
+                    normal = Vector::vec_scaled(Vector::new(-direction.y(), direction.x()), half_width / length);
+                }
+                self.vertices.push(Vector::vec_sum(points[i], normal));
+                self.vertices.push(Vector::vec_diff(points[i], normal));
+            }
+        }
+
+        let args = self.args(points.len());
+        let entity = entity_list.get_entity_unchecked(self.index);
+        entity.write_vertices(&self.vertices);
+        // A single point has nothing to join
+        entity.set_index_count(if points.len() > 1 { self.vertices.len() as Index } else { 0 });
+        entity.send_shader_args(args.bytes());
+    }
+
+    /// Stops drawing the trail, without removing its entity
+    pub fn hide(&mut self, entity_list: &mut EntityList) {
+        self.points.clear();
+        entity_list.get_entity_unchecked(self.index).set_index_count(0);
+    }
+
+    /// Deletes the entity drawing the trail
+    pub fn remove(self, entity_list: &mut EntityList) {
+        entity_list.delete_entity(self.index);
+    }
+}
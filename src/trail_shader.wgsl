@@ -0,0 +1,51 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+
+struct TrailArgs {
+    head_color: vec4<f32>,
+    tail_color: vec4<f32>,
+    // Points in the trail, the newest one first
+    points: u32,
+    // 1 for lines, 2 for ribbons
+    vertices_per_point: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> transformation_matrix: mat4x4<f32>;
+
+@group(1) @binding(1)
+var<uniform> shader_args: TrailArgs;
+
+// World to clip space, shared by every entity
+@group(2) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+@vertex
+fn vertex(
+    vertex_input: VertexInput,
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4(vertex_input.position, 1.0, 1.0) * transformation_matrix * camera;
+    // 0 at the head, 1 at the end of the tail
+    let age = f32(vertex_index / shader_args.vertices_per_point) / max(f32(shader_args.points) - 1.0, 1.0);
+    output.color = mix(shader_args.head_color, shader_args.tail_color, age);
+    return output;
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> FragmentOutput {
+    var output: FragmentOutput;
+    output.color = input.color;
+    return output;
+}
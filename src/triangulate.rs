@@ -0,0 +1,196 @@
+// Ear clipping for simple polygons, concave or not, wound either way, with or without holes.
+// Holes are first joined to the outline by a pair of edges to one of its vertices, turning the whole
+// into a single outline, which then has its ears, triangles without any other vertex in them, cut off one at a time
+
+use crate::utils::{
+    defaults::{ Float, Index },
+    Vector
+};
+
+/// Area of the polygon, positive when wound counter-clockwise
+pub fn signed_area(points: &[Vector<Float>]) -> Float {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        area += a.x() * b.y() - b.x() * a.y();
+    }
+    area * 0.5
+}
+
+/// Sum of the areas of the triangles, counting clockwise ones as negative
+pub fn triangles_area(vertices: &[Vector<Float>], indices: &[Index]) -> Float {
+    indices
+        .chunks_exact(3)
+        .map(|triangle| cross(vertices[triangle[0] as usize], vertices[triangle[1] as usize], vertices[triangle[2] as usize]) * 0.5)
+        .sum()
+}
+
+/// Triangle list of indices into `outline`, all wound counter-clockwise like everything else drawn
+pub fn triangulate(outline: &[Vector<Float>]) -> Vec<Index> {
+    if outline.len() < 3 { return Vec::new(); }
+    clip_ears(outline, counter_clockwise(outline, 0))
+}
+
+/// Triangulates `outline` with `holes` cut out of it. Returns the vertices, the outline's followed by each hole's,
+/// and a counter-clockwise triangle list of indices into them, ready for `Entity::set_geometry`
+pub fn triangulate_with_holes(outline: &[Vector<Float>], holes: &[&[Vector<Float>]]) -> (Vec<Vector<Float>>, Vec<Index>) {
+    let mut vertices = outline.to_vec();
+    for hole in holes {
+        vertices.extend_from_slice(hole);
+    }
+    if outline.len() < 3 { return (vertices, Vec::new()); }
+
+    let mut ring = counter_clockwise(outline, 0);
+
+    // Holes wound the other way, each with the index of its rightmost vertex
+    let mut start = outline.len();
+    let mut hole_rings = Vec::new();
+    for hole in holes {
+        if hole.len() >= 3 {
+            let mut hole_ring = counter_clockwise(hole, start);
+            hole_ring.reverse();
+            let rightmost = (0..hole_ring.len())
+                .max_by(|&a, &b| vertices[hole_ring[a]].x().total_cmp(&vertices[hole_ring[b]].x()))
+                .unwrap();
+            hole_ring.rotate_left(rightmost);
+            hole_rings.push(hole_ring);
+        }
+        start += hole.len();
+    }
+
+    // Right to left, so a hole is never bridged across one that isn't joined yet
+    hole_rings.sort_by(|a, b| vertices[b[0]].x().total_cmp(&vertices[a[0]].x()));
+    for hole_ring in hole_rings {
+        let bridge = bridge_vertex(&vertices, &ring, vertices[hole_ring[0]]);
+        // In at the bridge, all the way around the hole and back out again
+        let mut joined = Vec::with_capacity(ring.len() + hole_ring.len() + 2);
+        joined.extend_from_slice(&ring[..=bridge]);
+        joined.extend_from_slice(&hole_ring);
+        joined.push(hole_ring[0]);
+        joined.extend_from_slice(&ring[bridge..]);
+        ring = joined;
+    }
+
+    let indices = clip_ears(&vertices, ring);
+    (vertices, indices)
+}
+
+// Twice the signed area of the triangle, positive when counter-clockwise
+fn cross(a: Vector<Float>, b: Vector<Float>, c: Vector<Float>) -> Float {
+    (b.x() - a.x()) * (c.y() - a.y()) - (b.y() - a.y()) * (c.x() - a.x())
+}
+
+// Indices of `points`, offset by `start`, in counter-clockwise order
+fn counter_clockwise(points: &[Vector<Float>], start: usize) -> Vec<usize> {
+    let mut ring: Vec<usize> = (start..start + points.len()).collect();
+    if signed_area(points) < 0.0 {
+        ring.reverse();
+    }
+    ring
+}
+
+// Whether `p` is inside the counter-clockwise triangle or on its edges
+fn in_triangle(a: Vector<Float>, b: Vector<Float>, c: Vector<Float>, p: Vector<Float>) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+// Position in `ring` of a vertex that can be joined to `point` without crossing an edge, `point` being
+// the rightmost vertex of a hole inside the ring
+fn bridge_vertex(vertices: &[Vector<Float>], ring: &[usize], point: Vector<Float>) -> usize {
+    // The closest edge a ray to the right of the point hits, and where
+    let mut closest: Option<(usize, Float)> = None;
+    for i in 0..ring.len() {
+        let (a, b) = (vertices[ring[i]], vertices[ring[(i + 1) % ring.len()]]);
+        // Counter-clockwise outlines cross the ray going up on its right, holes already joined going down
+        if (a.y() > point.y()) == (b.y() > point.y()) { continue; }
+        let x = a.x() + (point.y() - a.y()) / (b.y() - a.y()) * (b.x() - a.x());
+        if x >= point.x() && closest.is_none_or(|(_, closest_x)| x < closest_x) {
+            closest = Some((i, x));
+        }
+    }
+    let Some((edge, hit_x)) = closest else {
+        // Not inside the outline after all, joined to its closest vertex so at least nothing is lost
+        return (0..ring.len())
+            .min_by(|&a, &b| {
+                Vector::vec_diff(vertices[ring[a]], point).mag().total_cmp(&Vector::vec_diff(vertices[ring[b]], point).mag())
+            })
+            .unwrap();
+    };
+
+    // The end of the hit edge furthest right is a candidate, unless other vertices stick into the triangle
+    // between it, the point and the hit, in which case the one at the smallest angle to the ray is visible
+    let hit = Vector::new(hit_x, point.y());
+    let (a, b) = (edge, (edge + 1) % ring.len());
+    let mut best = if vertices[ring[a]].x() > vertices[ring[b]].x() { a } else { b };
+    let candidate = vertices[ring[best]];
+    let (corner_a, corner_b) = if candidate.y() < point.y() { (candidate, hit) } else { (hit, candidate) };
+    let mut best_angle = Float::INFINITY;
+    for i in 0..ring.len() {
+        let p = vertices[ring[i]];
+        if i == best || p.x() < point.x() || p.pos == candidate.pos { continue; }
+        // Only reflex vertices can block the view
+        let previous = vertices[ring[(i + ring.len() - 1) % ring.len()]];
+        let next = vertices[ring[(i + 1) % ring.len()]];
+        if cross(previous, p, next) > 0.0 { continue; }
+        if in_triangle(point, corner_a, corner_b, p) {
+            let delta = Vector::vec_diff(p, point);
+            let angle = delta.y().abs().atan2(delta.x());
+            if angle < best_angle {
+                best_angle = angle;
+                best = i;
+            }
+        }
+    }
+    best
+}
+
+fn clip_ears(vertices: &[Vector<Float>], mut ring: Vec<usize>) -> Vec<Index> {
+    let mut indices = Vec::with_capacity(ring.len().saturating_sub(2) * 3);
+    let mut i = 0;
+    // Vertices looked at since the last cut, a whole lap without any ear means the polygon isn't simple
+    let mut misses = 0;
+
+    while ring.len() > 3 {
+        let len = ring.len();
+        let (previous, current, next) = (ring[(i + len - 1) % len], ring[i % len], ring[(i + 1) % len]);
+
+        let ear = if misses < len {
+            is_ear(vertices, &ring, previous, current, next)
+        } else {
+            // Cutting something keeps it going, flat corners first as they add no area
+            misses >= 2 * len || cross(vertices[previous], vertices[current], vertices[next]) <= 0.0
+        };
+
+        if ear {
+            if cross(vertices[previous], vertices[current], vertices[next]) > 0.0 {
+                indices.extend_from_slice(&[previous as Index, current as Index, next as Index]);
+            }
+            ring.remove(i % len);
+            misses = 0;
+            // Back a step, the previous vertex might have become an ear
+            i = (i % len + len - 2) % (len - 1);
+        } else {
+            misses += 1;
+            i = (i + 1) % len;
+        }
+    }
+
+    if ring.len() == 3 && cross(vertices[ring[0]], vertices[ring[1]], vertices[ring[2]]) > 0.0 {
+        indices.extend(ring.iter().map(|&index| index as Index));
+    }
+    indices
+}
+
+fn is_ear(vertices: &[Vector<Float>], ring: &[usize], previous: usize, current: usize, next: usize) -> bool {
+    let (a, b, c) = (vertices[previous], vertices[current], vertices[next]);
+    // Reflex or flat corners aren't ears
+    if cross(a, b, c) <= 0.0 { return false; }
+
+    // No other vertex may be inside, apart from copies of the corners left by bridges to holes
+    !ring.iter().any(|&other| {
+        let p = vertices[other];
+        other != previous && other != current && other != next
+            && p.pos != a.pos && p.pos != b.pos && p.pos != c.pos
+            && in_triangle(a, b, c, p)
+    })
+}
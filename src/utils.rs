@@ -12,10 +12,10 @@ use std::ops::{
 };
 
 use defaults::{
-    Float, UInt, Index
+    Float, UInt
 };
 
-use crate::vertex::Vertex;
+use crate::vertex::VertexFormat;
 
 pub fn as_u8_slice<T>(p: &[T]) -> &[u8] {
     unsafe {
@@ -33,6 +33,7 @@ pub struct Mat4x4 {
 }
 
 impl Mat4x4 {
+    #[allow(clippy::too_many_arguments, clippy::just_underscores_and_digits)]
     pub fn new(
          _0: Float,  _1: Float,  _2: Float,  _3: Float, 
          _4: Float,  _5: Float,  _6: Float,  _7: Float, 
@@ -131,9 +132,13 @@ impl Polygon {
     pub fn new(vertices: Vec<Vector<Float>>, indices: Vec<UInt>) -> Self {
         Polygon { vertices, indices }
     }
+
+    pub fn vertices(&self) -> &[Vector<Float>] { &self.vertices }
+
+    pub fn indices(&self) -> &[UInt] { &self.indices }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(C)]
 pub struct Vector<T: Clone + Copy> {
     pub pos: [T; 2],
@@ -177,9 +182,18 @@ impl<T:
     pub fn vec_diff(vec1: Self, vec2: Self) -> Self {
         Self::new(vec1.x() - vec2.x(), vec1.y() - vec2.y())
     }
+
+    pub fn scale_vec(&mut self, factor: T) {
+        self.pos[0] = self.pos[0] * factor;
+        self.pos[1] = self.pos[1] * factor;
+    }
+
+    pub fn vec_scaled(vec: Self, factor: T) -> Self {
+        Self::new(vec.x() * factor, vec.y() * factor)
+    }
     
     pub fn mag(&self) -> T {
-        return self.pos[0] * self.pos[0] + self.pos[1] * self.pos[1];
+        self.pos[0] * self.pos[0] + self.pos[1] * self.pos[1]
     }
 
     pub fn normalize(&mut self) {
@@ -222,7 +236,7 @@ fn internal_gen_arc(sides: u16, radius: Float, center: Vector<Float>, phase: Flo
         points.push(pos);
         if done { break; }
     }
-    return points;
+    points
 }
 
 pub fn generate_regular_geometry(sides: u16, radius: Float, center: Vector<Float>, phase: Float) -> Vec<Vector<Float>> {
@@ -236,13 +250,13 @@ pub fn generate_arc(sides: u16, radius: Float, center: Vector<Float>, phase: Flo
 }
 
 // Generate a triangle set that uses the least possible triangles to fill a given set of points - probably
-// TODO: Use an actual algorithm that can handle concave polygons
+// Only right for convex polygons, `triangulate::triangulate` handles the rest
 pub fn generate_triangles(points: Vec<u32>) -> Vec<u32> {
     if points.len() < 3 { return Vec::new(); }
     let num_triangles = ((points.len() as Float + 0.5) / 2.0) as u16;
-    let mut tris = Vec::with_capacity(points.len() - 2 as usize);
+    let mut tris = Vec::with_capacity(points.len() - 2);
     let mut i = 0;
-    let mut new_compute_points = Vec::with_capacity((points.len() + 1) / 2);
+    let mut new_compute_points = Vec::with_capacity(points.len().div_ceil(2));
     loop {
         let pt = points[i];
         let next_pt = points[i + 1];
@@ -259,7 +273,7 @@ pub fn generate_triangles(points: Vec<u32>) -> Vec<u32> {
     }
     let res = generate_triangles(new_compute_points);
     tris.extend_from_slice(res.as_slice());
-    return tris;
+    tris
 }
 
 // Rendering specific
@@ -295,16 +309,77 @@ pub fn generate_shader_args_layout(device: &wgpu::Device) -> wgpu::BindGroupLayo
     })
 }
 
+pub fn generate_camera_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor { 
+        label: Some("Camera Bind Group Layout Desc"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform, 
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
 
 pub fn generate_render_pipeline(device: &wgpu::Device, format: wgpu::TextureFormat, shader: wgpu::ShaderModule) -> wgpu::RenderPipeline {
+    generate_render_pipeline_for_format(device, format, &shader, &VertexFormat::position())
+}
+
+// Same as `generate_render_pipeline`, for vertex buffers laid out as `vertex_format`
+pub fn generate_render_pipeline_for_format(
+    device: &wgpu::Device, 
+    format: wgpu::TextureFormat, 
+    shader: &wgpu::ShaderModule, 
+    vertex_format: &VertexFormat
+) -> wgpu::RenderPipeline {
+    generate_render_pipeline_with_buffers(device, format, shader, &[vertex_format.desc()], wgpu::PrimitiveTopology::TriangleList, false)
+}
+
+// Same as `generate_render_pipeline`, with a per-instance vertex buffer in slot 1
+pub fn generate_instanced_render_pipeline(
+    device: &wgpu::Device, 
+    format: wgpu::TextureFormat, 
+    shader: wgpu::ShaderModule, 
+    instance_layout: wgpu::VertexBufferLayout
+) -> wgpu::RenderPipeline {
+    generate_render_pipeline_with_buffers(
+        device, format, &shader, &[VertexFormat::position().desc(), instance_layout], wgpu::PrimitiveTopology::TriangleList, false
+    )
+}
+
+// Same as `generate_render_pipeline`, but the vertices are joined into a line or triangle strip and alpha blended
+// over whatever was drawn before. Strips are drawn from both sides
+pub fn generate_strip_render_pipeline(
+    device: &wgpu::Device, 
+    format: wgpu::TextureFormat, 
+    shader: wgpu::ShaderModule, 
+    topology: wgpu::PrimitiveTopology
+) -> wgpu::RenderPipeline {
+    assert!(topology.is_strip(), "{:?} is not a strip topology", topology);
+    generate_render_pipeline_with_buffers(device, format, &shader, &[VertexFormat::position().desc()], topology, true)
+}
+
+fn generate_render_pipeline_with_buffers(
+    device: &wgpu::Device, 
+    format: wgpu::TextureFormat, 
+    shader: &wgpu::ShaderModule, 
+    buffers: &[wgpu::VertexBufferLayout],
+    topology: wgpu::PrimitiveTopology,
+    blended: bool
+) -> wgpu::RenderPipeline {
 
     let transform_layout = generate_transform_layout(device);
     let shader_layout = generate_shader_args_layout(device);
+    let camera_layout = generate_camera_layout(device);
 
     let render_pipeline_layout =
     device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
-        bind_group_layouts: &[&transform_layout, &shader_layout],
+        bind_group_layouts: &[&transform_layout, &shader_layout, &camera_layout],
         push_constant_ranges: &[],
     });
 
@@ -312,24 +387,24 @@ pub fn generate_render_pipeline(device: &wgpu::Device, format: wgpu::TextureForm
         label: Some("Render Pipeline"),
         layout: Some(&render_pipeline_layout),
         vertex: wgpu::VertexState {
-            module: &shader,
+            module: shader,
             entry_point: "vertex",
-            buffers: &[Vertex::desc()],
+            buffers,
         },
         fragment: Some(wgpu::FragmentState {
-            module: &shader,
+            module: shader,
             entry_point: "fragment",
             targets: &[Some(wgpu::ColorTargetState {
                 format,
-                blend: Some(wgpu::BlendState::REPLACE),
+                blend: Some(if blended { wgpu::BlendState::ALPHA_BLENDING } else { wgpu::BlendState::REPLACE }),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
         primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            strip_index_format: None,
+            topology,
+            strip_index_format: if topology.is_strip() { Some(wgpu::IndexFormat::Uint32) } else { None },
             front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
+            cull_mode: if topology.is_strip() { None } else { Some(wgpu::Face::Back) },
             unclipped_depth: false,
             polygon_mode: wgpu::PolygonMode::Fill,
             conservative: false,
@@ -338,7 +413,8 @@ pub fn generate_render_pipeline(device: &wgpu::Device, format: wgpu::TextureForm
         multisample: wgpu::MultisampleState {
             count: 4,
             mask: !0,
-            alpha_to_coverage_enabled: true,
+            // Would dither blended alpha into the 4 samples
+            alpha_to_coverage_enabled: !blended,
         },
         multiview: None,
     })
@@ -1,98 +1,215 @@
+use std::mem::size_of;
+
 use wgpu::{
     vertex_attr_array, 
     VertexAttribute
 };
 
-use crate::utils::Vector;
+use crate::utils::{
+    as_u8_slice,
+    defaults::Float,
+    Vector
+};
+
+/// Something a vertex carries. Each kind has its own shader location, wherever it is in the vertex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    // 2 floats at location 0, always first
+    Position,
+    // Linear RGBA at location 1
+    Color,
+    // 2 floats at location 2
+    Uv,
+    // Anything else a custom shader reads, at locations 3 and up in the order given
+    Custom(wgpu::VertexFormat),
+}
+
+impl Attribute {
+    pub fn format(&self) -> wgpu::VertexFormat {
+        match self {
+            Attribute::Position | Attribute::Uv => wgpu::VertexFormat::Float32x2,
+            Attribute::Color => wgpu::VertexFormat::Float32x4,
+            Attribute::Custom(format) => *format,
+        }
+    }
+}
 
+/// The attributes of a vertex and how they are interleaved in a vertex buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexFormat {
+    attributes: Vec<Attribute>,
+    layout: Vec<VertexAttribute>,
+    stride: u64,
+}
+
+impl VertexFormat {
+    pub const COLOR_LOCATION: u32 = 1;
+    pub const UV_LOCATION: u32 = 2;
+    pub const FIRST_CUSTOM_LOCATION: u32 = 3;
+
+    /// Attributes are laid out in the order given, starting with the position
+    pub fn new(attributes: &[Attribute]) -> Self {
+        assert!(attributes.first() == Some(&Attribute::Position), "Vertex formats start with the position");
+
+        let mut layout = Vec::with_capacity(attributes.len());
+        let mut offset = 0;
+        let mut custom_location = Self::FIRST_CUSTOM_LOCATION;
+        for (i, attribute) in attributes.iter().enumerate() {
+            let shader_location = match attribute {
+                Attribute::Custom(_) => {
+                    custom_location += 1;
+                    custom_location - 1
+                }
+                _ => {
+                    assert!(!attributes[..i].contains(attribute), "{:?} is in the vertex format twice", attribute);
+                    match attribute {
+                        Attribute::Position => 0,
+                        Attribute::Color => Self::COLOR_LOCATION,
+                        _ => Self::UV_LOCATION,
+                    }
+                }
+            };
+            layout.push(VertexAttribute { format: attribute.format(), offset, shader_location });
+            offset += attribute.format().size();
+        }
+
+        Self { attributes: attributes.to_vec(), layout, stride: offset }
+    }
+
+    /// Bare positions, what most entities use
+    pub fn position() -> Self { Self::new(&[Attribute::Position]) }
+
+    pub fn position_color() -> Self { Self::new(&[Attribute::Position, Attribute::Color]) }
+
+    pub fn position_uv() -> Self { Self::new(&[Attribute::Position, Attribute::Uv]) }
+
+    pub fn position_color_uv() -> Self { Self::new(&[Attribute::Position, Attribute::Color, Attribute::Uv]) }
+
+    pub fn attributes(&self) -> &[Attribute] { &self.attributes }
+
+    pub fn has(&self, attribute: Attribute) -> bool { self.attributes.contains(&attribute) }
+
+    /// Bytes per vertex
+    pub fn stride(&self) -> u64 { self.stride }
+
+    pub fn desc(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.stride,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &self.layout,
+        }
+    }
+}
+
+/// Per-vertex data kept one attribute at a time, interleaved into a vertex buffer by `bytes`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexData {
+    positions: Vec<Vector<Float>>,
+    colors: Option<Vec<[f32; 4]>>,
+    uvs: Option<Vec<[f32; 2]>>,
+    // Raw bytes of each custom attribute, one after another for every vertex
+    custom: Vec<(wgpu::VertexFormat, Vec<u8>)>,
+}
+
+impl VertexData {
+    pub fn new(positions: &[Vector<Float>]) -> Self {
+        Self { positions: positions.to_vec(), colors: None, uvs: None, custom: Vec::new() }
+    }
+
+    /// One color per vertex, linear RGBA
+    pub fn with_colors(mut self, colors: &[[f32; 4]]) -> Self {
+        assert_eq!(colors.len(), self.len(), "Expected a color for each vertex");
+        self.colors = Some(colors.to_vec());
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: &[[f32; 2]]) -> Self {
+        assert_eq!(uvs.len(), self.len(), "Expected a UV for each vertex");
+        self.uvs = Some(uvs.to_vec());
+        self
+    }
+
+    /// Adds an attribute for custom shaders, `T` has to be laid out as `format`
+    pub fn with_custom<T: Copy>(mut self, format: wgpu::VertexFormat, values: &[T]) -> Self {
+        assert_eq!(size_of::<T>() as u64, format.size(), "Values don't match {:?}", format);
+        assert_eq!(values.len(), self.len(), "Expected a value for each vertex");
+        self.custom.push((format, as_u8_slice(values).to_vec()));
+        self
+    }
+
+    pub fn len(&self) -> usize { self.positions.len() }
+
+    pub fn is_empty(&self) -> bool { self.positions.is_empty() }
+
+    pub fn positions(&self) -> &[Vector<Float>] { &self.positions }
+
+    pub fn colors(&self) -> Option<&[[f32; 4]]> { self.colors.as_deref() }
+
+    pub fn uvs(&self) -> Option<&[[f32; 2]]> { self.uvs.as_deref() }
+
+    /// Position, then color, UV and custom attributes if there are any
+    pub fn format(&self) -> VertexFormat {
+        let mut attributes = vec![Attribute::Position];
+        if self.colors.is_some() { attributes.push(Attribute::Color); }
+        if self.uvs.is_some() { attributes.push(Attribute::Uv); }
+        attributes.extend(self.custom.iter().map(|(format, _)| Attribute::Custom(*format)));
+        VertexFormat::new(&attributes)
+    }
+
+    /// The vertex buffer contents, laid out as `format`
+    pub fn bytes(&self) -> Vec<u8> {
+        let format = self.format();
+        let mut bytes = Vec::with_capacity(format.stride() as usize * self.len());
+        for i in 0..self.len() {
+            bytes.extend_from_slice(as_u8_slice(&self.positions[i..i + 1]));
+            if let Some(colors) = &self.colors {
+                bytes.extend_from_slice(as_u8_slice(&colors[i..i + 1]));
+            }
+            if let Some(uvs) = &self.uvs {
+                bytes.extend_from_slice(as_u8_slice(&uvs[i..i + 1]));
+            }
+            for (format, values) in &self.custom {
+                let size = format.size() as usize;
+                bytes.extend_from_slice(&values[i * size..(i + 1) * size]);
+            }
+        }
+        bytes
+    }
+}
+
+/// A vertex with a color, laid out as `VertexFormat::position_color`. Mesh files are made of these, see `mesh`
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vertex {
-    pub(crate) position: [f32; 3],
-    pub(crate) color: [f32; 3],
+    pub position: [f32; 2],
+    pub color: [f32; 4],
 }
 
 impl Vertex {
 
     // Constants
-    const ATTRIBUTES: [VertexAttribute; 1] = vertex_attr_array![0 => Float32x2];
-    //, 1 => Float32x3];
+    const ATTRIBUTES: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x2, 1 => Float32x4];
 
     // Functions
+    /// Reads the mesh file at `filepath` into vertices and indices, panicking if it can't be loaded
+    #[deprecated(note = "use `mesh::Mesh::load`, which reports errors instead of panicking")]
     pub fn new(filepath: String) -> (Vec<Vertex>, Vec<u32>) {
-        println!("Reading vertex data file...");
-        let file_contents = std::fs::read_to_string(filepath).expect("Failed to read file");
-        let mut vertex_buffer = Vec::new(); 
-        println!("Opened vertex data file.");
-        let time = std::time::Instant::now();
-        let mut is_index_buffer = false;
-        let mut index_buffer = Vec::new();
-        // TODO: Is splitting and trimming faster, or is replacing \r\n and \r with \n faster?
-        // From unscientific tests, the latter is faster
-        file_contents
-            .replace("\r\n", "\n")
-            .replace("\r", "\n")
-            .split("\n")
-            // .split(&['\n', '\r'][..])
-            .for_each(|data: &str| {
-                // let data = data.trim();
-                // if data == "" { return; }
-
-                // Deal with index buffer contents
-                if data.trim() == "~" {
-                    is_index_buffer = true;
-                    return;
-                }
-
-                if is_index_buffer {
-                    data
-                        .split(" ")
-                        .for_each(|elem| {
-                            let index = elem.parse::<u32>().expect("Failed to parse index buffer!");
-                            index_buffer.push(index)
-                        });
-                    return;
-                }
-
-                // [v,v,v c,c,c]
-                // [v,v,v] can also be parsed with the color being white
-                let mut data_split = data.split(" ");
-                // vectors first
-                let position_data = data_split.next().expect("Failed to parse file. Expected position data");
-                let color_data = data_split.next().unwrap_or("1.0,1.0,1.0");
-                // Parse positions
-                let mut position = [0.0; 3];
-                let mut i = 0;
-                position_data
-                    .split(",")
-                    .for_each(|num: &str| {
-                        let parsed = num.parse::<f32>();
-                        position[i] = parsed.expect("Failed to parse position number");
-                        i += 1;
-                    });
-                // Parse colors
-                let mut color = [1.0; 3];
-                i = 0;
-                color_data
-                    .split(",")
-                    .for_each(|num: &str| {
-                        let parsed = num.parse::<f32>();
-                        color[i] = parsed.unwrap_or(1.0);
-                        i += 1;
-                    });
-                // Add to the vertex list
-                vertex_buffer.push(Vertex { position, color })
-            });
-        println!("Finished parsing vertex file in {:?}", std::time::Instant::now() - time);
-        (vertex_buffer, index_buffer)
+        let mesh = crate::mesh::Mesh::load(filepath).unwrap_or_else(|error| panic!("{}", error));
+        (mesh.vertices, mesh.indices)
     }
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vector<f32>>() as wgpu::BufferAddress,
+            array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
             attributes: &Self::ATTRIBUTES,
         }
     }
 
+    /// The vertices as `VertexData`, for `Entity::set_vertex_data`
+    pub fn to_vertex_data(vertices: &[Vertex]) -> VertexData {
+        let positions: Vec<Vector<Float>> = vertices.iter().map(|vertex| Vector { pos: vertex.position }).collect();
+        let colors: Vec<[f32; 4]> = vertices.iter().map(|vertex| vertex.color).collect();
+        VertexData::new(&positions).with_colors(&colors)
+    }
 }
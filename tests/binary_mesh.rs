@@ -0,0 +1,82 @@
+use renderer::{
+    binary_mesh::{ BinaryMesh, BinaryMeshError },
+    mesh::Mesh,
+    utils::Vector,
+    vertex::{ VertexData, VertexFormat },
+};
+
+fn arrow() -> Mesh {
+    Mesh::load(concat!(env!("CARGO_MANIFEST_DIR"), "/meshes/arrow.mesh")).unwrap()
+}
+
+fn encode(mesh: &BinaryMesh) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    mesh.write(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trip() {
+    let mesh = arrow();
+    let binary = BinaryMesh::from_mesh("arrow", &mesh).with_metadata("source", "arrow.mesh");
+    let bytes = encode(&binary);
+    // Blobs start 4 byte aligned
+    assert_eq!((bytes.len() - binary.vertex_bytes().len() - binary.index_bytes().len()) % 4, 0);
+
+    let loaded = BinaryMesh::from_bytes(bytes).unwrap();
+    assert_eq!(loaded.name(), "arrow");
+    assert_eq!(loaded.metadata_value("source"), Some("arrow.mesh"));
+    assert_eq!(loaded.format(), &VertexFormat::position_color());
+    assert_eq!((loaded.vertex_count(), loaded.index_count()), (7, 9));
+    assert_eq!(loaded.vertex_bytes(), mesh.vertex_data().bytes());
+    assert_eq!(loaded.indices(), mesh.indices);
+
+    // Custom attributes keep their format
+    let data = VertexData::new(&[Vector::new(0.0, 0.0); 3])
+        .with_uvs(&[[0.0, 1.0]; 3])
+        .with_custom(wgpu::VertexFormat::Uint16x2, &[[1u16, 2]; 3]);
+    let custom = BinaryMesh::new("", &data, &[0, 1, 2]);
+    let loaded = BinaryMesh::from_bytes(encode(&custom)).unwrap();
+    assert_eq!(loaded.format(), &data.format());
+    assert_eq!(loaded.vertex_bytes(), data.bytes());
+}
+
+#[test]
+fn rejects_bad_files() {
+    let bytes = encode(&BinaryMesh::from_mesh("arrow", &arrow()));
+
+    assert!(matches!(BinaryMesh::from_bytes(b"# a text mesh".to_vec()), Err(BinaryMeshError::NotAMesh)));
+
+    let mut version = bytes.clone();
+    version[8] = 9;
+    assert!(matches!(BinaryMesh::from_bytes(version), Err(BinaryMeshError::UnsupportedVersion(9))));
+
+    assert!(matches!(BinaryMesh::from_bytes(bytes[..bytes.len() - 1].to_vec()), Err(BinaryMeshError::Corrupt(_))));
+    assert!(matches!(BinaryMesh::from_bytes(bytes[..14].to_vec()), Err(BinaryMeshError::Corrupt(_))));
+
+    // The last index points past the vertices
+    let mut index = bytes.clone();
+    let last = index.len() - 4;
+    index[last..].copy_from_slice(&7u32.to_le_bytes());
+    match BinaryMesh::from_bytes(index) {
+        Err(BinaryMeshError::Corrupt(message)) => assert_eq!(message, "Index 7 is out of range for 7 vertices"),
+        other => panic!("expected a corrupt mesh, got {:?}", other),
+    }
+}
+
+#[test]
+fn converter() {
+    let output = std::env::temp_dir().join(format!("mesh_convert_test_{}.bmesh", std::process::id()));
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_mesh_convert"))
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/meshes/arrow.mesh"))
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let converted = BinaryMesh::load(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert_eq!(converted.name(), "arrow");
+    assert_eq!(converted.metadata_value("source"), Some("arrow.mesh"));
+    assert_eq!(converted.indices(), arrow().indices);
+}
@@ -0,0 +1,129 @@
+use renderer::{
+    camera::{ AspectMode, CameraMode },
+    scene::Scene,
+    utils::{ Mat4x4, Vector },
+};
+
+mod common;
+
+fn close(a: Vector<f32>, b: Vector<f32>) -> bool {
+    Vector::vec_diff(a, b).mag().sqrt() < 1e-4
+}
+
+#[test]
+fn camera_behaviour() {
+    let mut renderer = common::headless(200, 100);
+    let entity_list = renderer.entities();
+
+    let camera = entity_list.camera_mut();
+    assert_eq!(camera.screen_size().pos, [200.0, 100.0]);
+    camera.set_center(Vector::new(1.0, -2.0));
+    camera.set_zoom(4.0);
+    // The bounds fit the height, a world unit is 50 pixels at a zoom of 1 on both axes
+    let world = Vector::new(1.25, -1.5);
+    assert!(close(camera.world_to_screen(world), Vector::new(150.0, -50.0)));
+    assert!(close(camera.screen_to_world(camera.world_to_screen(world)), world));
+
+    // Whatever is under the cursor stays there
+    let cursor = Vector::new(150.0, 40.0);
+    let under_cursor = camera.screen_to_world(cursor);
+    camera.zoom_at(1.5, cursor);
+    assert!((camera.zoom() - 6.0).abs() < 1e-5);
+    assert!(close(camera.screen_to_world(cursor), under_cursor));
+
+    let before = camera.screen_to_world(Vector::new(100.0, 50.0));
+    camera.pan(Vector::new(30.0, -20.0));
+    assert!(close(camera.screen_to_world(Vector::new(130.0, 30.0)), before));
+
+    camera.set_zoom(0.0);
+    assert!(camera.zoom() > 0.0);
+
+    let mut transform = Mat4x4::identity();
+    transform.translate_to(Vector::new(3.0, 4.0));
+    entity_list.add_entity().set_transform(transform);
+    entity_list.camera_mut().set_mode(CameraMode::FollowEntity(0));
+    renderer.render_offscreen();
+    assert!(close(renderer.entities().camera().center(), Vector::new(3.0, 4.0)));
+
+    let camera = renderer.entities().camera_mut();
+    camera.set_center_of_mass(Vector::new(-1.0, 0.5));
+    camera.set_mode(CameraMode::FollowCenterOfMass);
+    renderer.render_offscreen();
+    assert!(close(renderer.entities().camera().center(), Vector::new(-1.0, 0.5)));
+
+    // Following something that no longer exists
+    renderer.entities().camera_mut().set_mode(CameraMode::FollowEntity(7));
+    renderer.render_offscreen();
+    assert_eq!(renderer.entities().camera().mode(), CameraMode::Free);
+}
+
+#[test]
+fn tab_skips_trails_and_deleted_bodies() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    // Each body is followed by its trail
+    let scene = Scene::parse("
+        trails 8
+        body mass=1 radius=0.1 position=-0.5,0
+        body mass=1 radius=0.1 position=0,0
+        body mass=1 radius=0.1 position=0.5,0
+    ").unwrap();
+    let circles = scene.spawn(entity_list);
+    let bodies: Vec<usize> = circles.iter().map(|circle| circle.entity_index()).collect();
+    entity_list.delete_entity(bodies[1]);
+
+    assert_eq!(entity_list.next_followable(None), Some(bodies[0]));
+    assert_eq!(entity_list.next_followable(Some(bodies[0])), Some(bodies[2]));
+    assert_eq!(entity_list.next_followable(Some(bodies[2])), Some(bodies[0]));
+}
+
+#[test]
+fn deleting_the_followed_entity_frees_the_camera() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    let followed = entity_list.next_index();
+    entity_list.add_entity();
+    let generation = entity_list.generation(followed);
+    entity_list.camera_mut().set_mode(CameraMode::FollowEntity(followed));
+    entity_list.delete_entity(followed);
+    assert_eq!(entity_list.camera().mode(), CameraMode::Free);
+    assert_eq!(entity_list.generation(followed), None);
+
+    // The next entity takes the slot, under a new generation and without being followed
+    assert_eq!(entity_list.next_index(), followed);
+    entity_list.add_entity();
+    assert!(entity_list.generation(followed).is_some() && entity_list.generation(followed) != generation);
+    assert_eq!(entity_list.camera().mode(), CameraMode::Free);
+}
+
+#[test]
+fn aspect_modes() {
+    let mut renderer = common::headless(200, 100);
+    let camera = renderer.entities().camera_mut();
+    camera.set_bounds(Vector::new(2.0, 1.0));
+
+    // Same shape as the window, every mode agrees
+    for mode in [AspectMode::Fit, AspectMode::Fill, AspectMode::Letterbox, AspectMode::Stretch] {
+        camera.set_aspect_mode(mode);
+        assert!(close(camera.screen_to_world(Vector::new(200.0, 0.0)), Vector::new(2.0, 1.0)), "{:?}", mode);
+    }
+
+    camera.set_bounds(Vector::new(1.0, 1.0));
+    camera.set_aspect_mode(AspectMode::Fit);
+    assert!(close(camera.screen_to_world(Vector::new(200.0, 0.0)), Vector::new(2.0, 1.0)));
+    camera.set_aspect_mode(AspectMode::Fill);
+    assert!(close(camera.screen_to_world(Vector::new(200.0, 0.0)), Vector::new(1.0, 0.5)));
+    camera.set_aspect_mode(AspectMode::Stretch);
+    assert!(close(camera.screen_to_world(Vector::new(200.0, 0.0)), Vector::new(1.0, 1.0)));
+    camera.set_aspect_mode(AspectMode::Letterbox);
+    assert_eq!(camera.viewport(), [50.0, 0.0, 100.0, 100.0]);
+    assert!(close(camera.screen_to_world(Vector::new(150.0, 0.0)), Vector::new(1.0, 1.0)));
+
+    renderer.resize(winit::dpi::PhysicalSize::new(100, 300));
+    let camera = renderer.entities().camera_mut();
+    assert_eq!(camera.viewport(), [0.0, 100.0, 100.0, 100.0]);
+    camera.set_aspect_mode(AspectMode::Fit);
+    assert!(close(camera.screen_to_world(Vector::new(100.0, 0.0)), Vector::new(1.0, 3.0)));
+}
@@ -0,0 +1,145 @@
+use renderer::{
+    circle::Circle,
+    collision::{ self, CollisionResponse },
+    scene::Scene,
+    utils::{ defaults::Float, Vector },
+};
+
+mod common;
+
+// Head on, closing at 2 units per second
+fn head_on() -> ([Vector<Float>; 2], [Vector<Float>; 2]) {
+    ([Vector::new(-0.09, 0.0), Vector::new(0.09, 0.0)], [Vector::new(1.0, 0.0), Vector::new(-1.0, 0.0)])
+}
+
+fn momentum(velocities: &[Vector<Float>], masses: &[Float]) -> Vector<Float> {
+    let mut momentum = Vector::new(0.0, 0.0);
+    for (velocity, &mass) in velocities.iter().zip(masses) {
+        momentum.add_vec(Vector::vec_scaled(*velocity, mass));
+    }
+    momentum
+}
+
+#[test]
+fn elastic_bounce_swaps_equal_velocities() {
+    let (mut positions, mut velocities) = head_on();
+    let (mut masses, mut radii) = ([1.0, 1.0], [0.1, 0.1]);
+    let absorbed = collision::resolve(
+        CollisionResponse::Elastic { restitution: 1.0 }, &mut positions, &mut velocities, &mut masses, &mut radii
+    );
+
+    assert!(absorbed.is_empty());
+    assert!((velocities[0].x() + 1.0).abs() < 1e-6 && (velocities[1].x() - 1.0).abs() < 1e-6);
+    // No longer overlapping
+    assert!(Vector::vec_diff(positions[1], positions[0]).mag().sqrt() >= 0.2 - 1e-6);
+}
+
+#[test]
+fn restitution_scales_separation_speed() {
+    let (mut positions, mut velocities) = head_on();
+    let masses = [3.0, 1.0];
+    let contacts = collision::contacts(&positions, &[0.1, 0.1]);
+    collision::bounce(&contacts, &mut positions, &mut velocities, &masses, 0.5);
+
+    let separation = velocities[1].x() - velocities[0].x();
+    assert!((separation - 1.0).abs() < 1e-5, "separating at {}", separation);
+    assert!((momentum(&velocities, &masses).x() - 2.0).abs() < 1e-5);
+}
+
+#[test]
+fn massless_bodies_are_immovable() {
+    let (mut positions, mut velocities) = head_on();
+    let contacts = collision::contacts(&positions, &[0.1, 0.1]);
+    collision::bounce(&contacts, &mut positions, &mut velocities, &[0.0, 1.0], 1.0);
+    // The moving body bounces straight back, the other stays put
+    assert_eq!((positions[0].x(), velocities[0].x()), (-0.09, 1.0));
+    assert!((velocities[1].x() - 3.0).abs() < 1e-5, "bounced at {}", velocities[1].x());
+    assert!(positions[1].x() >= 0.11 - 1e-6);
+
+    // Neither can move, so nothing changes and nothing turns NaN
+    let (mut positions, mut velocities) = head_on();
+    collision::bounce(&contacts, &mut positions, &mut velocities, &[0.0, 0.0], 1.0);
+    assert_eq!((positions, velocities), head_on());
+}
+
+#[test]
+fn merge_conserves_mass_momentum_and_area() {
+    let (mut positions, mut velocities) = head_on();
+    let (mut masses, mut radii) = ([3.0, 1.0], [0.1, 0.1]);
+    let before = momentum(&velocities, &masses);
+    let absorbed = collision::resolve(CollisionResponse::Merge, &mut positions, &mut velocities, &mut masses, &mut radii);
+
+    assert_eq!(absorbed, vec![1]);
+    assert_eq!(masses[0], 4.0);
+    assert!((masses[0] * velocities[0].x() - before.x()).abs() < 1e-6);
+    assert!((radii[0] - (0.02 as Float).sqrt()).abs() < 1e-6);
+    // Center of mass
+    assert!((positions[0].x() - (-0.09 * 3.0 + 0.09) / 4.0).abs() < 1e-6);
+}
+
+#[test]
+fn merged_bodies_free_their_entities() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    let scene = Scene::parse("
+        trails 8
+        body mass=3 radius=0.1 position=-0.05,0
+        body mass=1 radius=0.1 position=0.05,0
+        body mass=1 radius=0.1 position=0.8,0
+    ").unwrap();
+    let mut circles = scene.spawn(entity_list);
+    // A circle and a trail each
+    assert_eq!(entity_list.count(), 6);
+    let absorbed = circles[1].entity_index();
+    let kept: Vec<usize> = [0, 2].iter().map(|&i| circles[i].entity_index()).collect();
+
+    Circle::collide(&mut circles, entity_list, CollisionResponse::Merge);
+    assert_eq!(circles.len(), 2);
+    assert_eq!(entity_list.count(), 4);
+    assert!(entity_list.get_entity(absorbed).is_none());
+    assert_eq!(circles.iter().map(|circle| circle.entity_index()).collect::<Vec<_>>(), kept);
+
+    // Freed slots are taken before the list grows
+    let zero = Vector::new(0.0, 0.0);
+    let spawned = Circle::new(entity_list, 1, 0.1, Vector::new(-0.8, 0.0), zero, zero);
+    assert!(spawned.entity_index() < 6);
+    assert_eq!(entity_list.count(), 5);
+}
+
+#[test]
+fn ignore_leaves_bodies_alone() {
+    let (mut positions, mut velocities) = head_on();
+    let (mut masses, mut radii) = ([1.0, 1.0], [0.1, 0.1]);
+    collision::resolve(CollisionResponse::Ignore, &mut positions, &mut velocities, &mut masses, &mut radii);
+    let (start_positions, start_velocities) = head_on();
+    assert_eq!(positions.map(|position| position.pos), start_positions.map(|position| position.pos));
+    assert_eq!(velocities.map(|velocity| velocity.pos), start_velocities.map(|velocity| velocity.pos));
+}
+
+#[test]
+fn broad_phase_finds_every_pair() {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 40) as Float / (1u64 << 24) as Float
+    };
+    let positions: Vec<Vector<Float>> = (0..500).map(|_| Vector::new(next() * 2.0 - 1.0, next() * 2.0 - 1.0)).collect();
+    let radii: Vec<Float> = (0..500).map(|_| 0.01 + next() * 0.03).collect();
+
+    let mut expected = Vec::new();
+    for i in 0..positions.len() {
+        for j in i + 1..positions.len() {
+            let reach = radii[i] + radii[j];
+            if Vector::vec_diff(positions[i], positions[j]).mag() < reach * reach {
+                expected.push((i, j));
+            }
+        }
+    }
+
+    let found: Vec<(usize, usize)> = collision::contacts(&positions, &radii).iter().map(|contact| (contact.a, contact.b)).collect();
+    assert!(!expected.is_empty());
+    assert_eq!(found, expected);
+}
@@ -0,0 +1,19 @@
+// Shared by the tests that render or otherwise need a GPU
+
+use pollster::FutureExt as _;
+use renderer::{
+    base_renderer::BaseRenderer,
+    entity::EntityList,
+    input::InputState,
+    timestep::FrameTime,
+};
+
+pub type HeadlessRenderer = BaseRenderer<'static, fn(&mut EntityList, &InputState, FrameTime)>;
+
+/// A headless renderer of the given size. Panics when there is no adapter, not even a fallback one,
+/// so tests needing a GPU fail instead of passing without having checked anything
+pub fn headless(width: u32, height: u32) -> HeadlessRenderer {
+    HeadlessRenderer::new_headless(width, height)
+        .block_on()
+        .expect("No adapter available, GPU tests need at least a fallback (software) adapter")
+}
@@ -0,0 +1,94 @@
+use renderer::{
+    diagnostics::{ Diagnostics, DiagnosticsLog },
+    gravity::{ self, BruteForce, GravitySolver },
+    integrator::{ Integrator, VelocityVerlet },
+    utils::{ defaults::Float, Vector },
+};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-6 * b.abs().max(1.0)
+}
+
+#[test]
+fn two_bodies() {
+    let positions = [Vector::new(-1.0, 0.0), Vector::new(1.0, 0.0)];
+    let velocities = [Vector::new(0.0, -1.0), Vector::new(0.0, 2.0)];
+    let masses = [2.0, 1.0];
+    let diagnostics = Diagnostics::measure(&positions, &velocities, &masses);
+
+    assert!(close(diagnostics.kinetic_energy, 0.5 * 2.0 * 1.0 + 0.5 * 1.0 * 4.0));
+    assert!(close(diagnostics.potential_energy, -(gravity::G as f64) * 2.0 / 2.0));
+    assert!(close(diagnostics.momentum[0], 0.0) && close(diagnostics.momentum[1], 0.0));
+    // -1 * 2 * -1 + 1 * 1 * 2
+    assert!(close(diagnostics.angular_momentum, 4.0));
+    assert!(close(diagnostics.center_of_mass[0], -1.0 / 3.0) && close(diagnostics.center_of_mass[1], 0.0));
+}
+
+#[test]
+fn conserved_over_a_run() {
+    let masses = [5.0, 1.0, 1.0];
+    let mut positions = [Vector::new(0.0, 0.0), Vector::new(0.5, 0.0), Vector::new(-0.3, 0.1)];
+    let mut velocities = [Vector::new(0.0, 0.0), Vector::new(0.0, 0.06), Vector::new(0.01, -0.07)];
+
+    let mut log = DiagnosticsLog::new();
+    let dt = 1.0 / 60.0;
+    log.record(0.0, Diagnostics::measure(&positions, &velocities, &masses));
+    for step in 1..=600 {
+        VelocityVerlet.step(&mut positions, &mut velocities, dt, &mut |positions, accelerations| {
+            BruteForce::default().accelerations(positions, &masses, accelerations)
+        });
+        log.record(step as f64 * dt as f64, Diagnostics::measure(&positions, &velocities, &masses));
+    }
+
+    let (first, last) = (log.samples()[0].1, log.samples().last().unwrap().1);
+    assert!((last.momentum[0] - first.momentum[0]).abs() < 1e-5);
+    assert!((last.momentum[1] - first.momentum[1]).abs() < 1e-5);
+    assert!((last.angular_momentum - first.angular_momentum).abs() < 1e-5);
+    assert!(log.energy_drift() < 1e-2, "energy drifted by {}", log.energy_drift());
+}
+
+#[test]
+fn csv_has_a_row_per_sample() {
+    let positions = [Vector::new(0.0, 0.0), Vector::new(1.0, 0.0)];
+    let velocities = [Vector::new(0.0, 0.0); 2];
+    let masses: [Float; 2] = [1.0, 1.0];
+
+    let mut log = DiagnosticsLog::new();
+    log.record(0.0, Diagnostics::measure(&positions, &velocities, &masses));
+    log.record(0.5, Diagnostics::measure(&positions, &velocities, &masses));
+
+    let mut csv = Vec::new();
+    log.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("time,kinetic_energy,"));
+    assert!(lines[2].starts_with("0.5,0,"));
+    assert!(lines.iter().all(|line| line.split(',').count() == 9));
+}
+
+#[test]
+fn drift_from_zero_energy_is_absolute() {
+    let at_energy = |kinetic_energy: f64| Diagnostics {
+        kinetic_energy,
+        potential_energy: 0.0,
+        momentum: [0.0, 0.0],
+        angular_momentum: 0.0,
+        center_of_mass: [0.0, 0.0],
+        mass: 1.0,
+    };
+
+    let mut log = DiagnosticsLog::new();
+    log.record(0.0, at_energy(0.0));
+    log.record(1.0, at_energy(0.0));
+    assert_eq!(log.energy_drift(), 0.0);
+    log.record(2.0, at_energy(0.25));
+    assert_eq!(log.energy_drift(), 0.25);
+
+    // Bound bodies have a negative total, drift is relative to its magnitude
+    let mut log = DiagnosticsLog::new();
+    log.record(0.0, at_energy(-2.0));
+    log.record(1.0, at_energy(-1.0));
+    assert_eq!(log.energy_drift(), 0.5);
+}
@@ -0,0 +1,293 @@
+// Golden image tests: scenes are rendered headlessly and compared against the references in `tests/golden/`.
+// Run with `UPDATE_GOLDEN=1` to (re)generate the references. Diffs of failing tests are written to `target/golden-diff/`
+
+use std::path::PathBuf;
+
+use renderer::{
+    binary_mesh::BinaryMesh,
+    camera::AspectMode,
+    circle::Circle,
+    entity::EntityList,
+    image::{ self, ImageFormat },
+    instanced::{ Instance, Instanced },
+    mesh::Mesh,
+    path::Path,
+    trail::{ Trail, TrailStyle },
+    utils::{ self, defaults::PI, Mat4x4, Vector },
+    vertex::VertexData,
+};
+
+mod common;
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 128;
+
+struct Golden {
+    name: &'static str,
+    width: u32,
+    height: u32,
+    // Largest allowed difference of any channel of a pixel
+    tolerance: u8,
+    // Pixels allowed to exceed the tolerance, to leave some room for rasterizers disagreeing on edges
+    max_mismatched: usize,
+}
+
+impl Golden {
+    fn new(name: &'static str) -> Self {
+        Self { name, width: WIDTH, height: HEIGHT, tolerance: 2, max_mismatched: 0 }
+    }
+
+    fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    fn max_mismatched(mut self, pixels: usize) -> Self {
+        self.max_mismatched = pixels;
+        self
+    }
+
+    fn reference_path(&self) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.ppm", self.name))
+    }
+
+    fn diff_path(&self) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden-diff").join(format!("{}.png", self.name))
+    }
+
+    /// Renders the scene built by `build` and compares it with the reference
+    fn check(&self, build: impl FnOnce(&mut EntityList)) {
+        let mut renderer = common::headless(self.width, self.height);
+
+        build(renderer.entities());
+        let actual = renderer.render_offscreen();
+
+        let reference_path = self.reference_path();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+            image::save_image(&reference_path, ImageFormat::Ppm, self.width, self.height, &actual).unwrap();
+            println!("Wrote golden image {}", reference_path.display());
+            return;
+        }
+
+        let data = std::fs::read(&reference_path).unwrap_or_else(|error| panic!(
+            "Golden image `{}` has no reference at {} ({}), run with `UPDATE_GOLDEN=1` to create it",
+            self.name, reference_path.display(), error
+        ));
+        let (width, height, expected) = image::decode_ppm(&data)
+            .unwrap_or_else(|| panic!("{} is not a binary PPM", reference_path.display()));
+        assert_eq!((width, height), (self.width, self.height), "Golden image `{}` has a different size", self.name);
+
+        let (mismatched, diff) = self.diff(&expected, &actual);
+        if mismatched > self.max_mismatched {
+            let diff_path = self.diff_path();
+            std::fs::create_dir_all(diff_path.parent().unwrap()).unwrap();
+            image::save_image(&diff_path, ImageFormat::Png, self.width, self.height, &diff).unwrap();
+            panic!(
+                "Golden image `{}`: {} pixels differ by more than {} (at most {} allowed), diff written to {}",
+                self.name, mismatched, self.tolerance, self.max_mismatched, diff_path.display()
+            );
+        }
+    }
+
+    // Mismatched pixels are marked red on top of a dimmed copy of the actual frame
+    fn diff(&self, expected: &[u8], actual: &[u8]) -> (usize, Vec<u8>) {
+        let mut mismatched = 0;
+        let mut diff = Vec::with_capacity(actual.len());
+        for (expected, actual) in expected.chunks(4).zip(actual.chunks(4)) {
+            let exceeds = expected
+                .iter()
+                .zip(actual)
+                .take(3)
+                .any(|(&e, &a)| e.abs_diff(a) > self.tolerance);
+
+            if exceeds {
+                mismatched += 1;
+                diff.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                diff.extend_from_slice(&[actual[0] / 4, actual[1] / 4, actual[2] / 4, 255]);
+            }
+        }
+        (mismatched, diff)
+    }
+}
+
+fn add_polygon(entity_list: &mut EntityList, sides: u16, radius: f32, position: Vector<f32>) {
+    let verts = utils::generate_regular_geometry(sides, radius, Vector::new(0.0, 0.0), 0.0);
+    let indices = (0..verts.len() as u32).collect();
+    let tris = utils::generate_triangles(indices);
+
+    let mut transform = Mat4x4::identity();
+    transform.translate_to(position);
+
+    let entity = entity_list.add_entity();
+    entity.set_geometry(&verts, &tris);
+    entity.set_transform(transform);
+}
+
+#[test]
+fn empty_scene() {
+    Golden::new("empty_scene").check(|_| {});
+}
+
+#[test]
+fn three_circles() {
+    Golden::new("three_circles").max_mismatched(8).check(|entity_list| {
+        let zero = Vector::new(0.0, 0.0);
+        Circle::new(entity_list, 1, 0.2, Vector::new(0.0, 0.5), zero, zero);
+        Circle::new(entity_list, 1, 0.2, Vector::new(-0.433, -0.25), zero, zero);
+        Circle::new(entity_list, 1, 0.2, Vector::new(0.433, -0.25), zero, zero);
+    });
+}
+
+#[test]
+fn regular_polygons() {
+    Golden::new("regular_polygons").max_mismatched(8).check(|entity_list| {
+        add_polygon(entity_list, 3, 0.3, Vector::new(-0.5, 0.5));
+        add_polygon(entity_list, 4, 0.3, Vector::new(0.5, 0.5));
+        add_polygon(entity_list, 5, 0.3, Vector::new(-0.5, -0.5));
+        add_polygon(entity_list, 8, 0.3, Vector::new(0.5, -0.5));
+    });
+}
+
+#[test]
+fn instanced_grid() {
+    Golden::new("instanced_grid").max_mismatched(8).check(|entity_list| {
+        let verts = utils::generate_regular_geometry(4, 0.06, Vector::new(0.0, 0.0), 0.0);
+        let tris = utils::generate_triangles((0..verts.len() as u32).collect());
+        let mut grid = Instanced::new(entity_list, &verts, &tris);
+
+        // More than the initial capacity, so the buffer has to grow
+        for row in 0..10 {
+            for column in 0..10 {
+                let position = Vector::new(column as f32 * 0.18 - 0.81, row as f32 * 0.18 - 0.81);
+                let mut instance = Instance::new(position, [column as f32 / 9.0, row as f32 / 9.0, 0.5]);
+                instance.transform.rotate_to((row * 10 + column) as f32 * 0.05);
+                grid.push(instance);
+            }
+        }
+        grid.upload(entity_list);
+    });
+}
+
+#[test]
+fn camera_zoomed_in_off_screen() {
+    Golden::new("camera_zoomed_in_off_screen").max_mismatched(8).check(|entity_list| {
+        // Both are out of clip space until the camera moves over
+        add_polygon(entity_list, 6, 0.2, Vector::new(1.5, 1.5));
+        add_polygon(entity_list, 3, 0.1, Vector::new(1.9, 1.5));
+        let camera = entity_list.camera_mut();
+        camera.set_center(Vector::new(1.6, 1.5));
+        camera.set_zoom(2.0);
+    });
+}
+
+#[test]
+fn wide_window_keeps_circles_round() {
+    Golden::new("wide_window_keeps_circles_round").size(192, 96).max_mismatched(8).check(|entity_list| {
+        add_polygon(entity_list, 24, 0.8, Vector::new(0.0, 0.0));
+        add_polygon(entity_list, 24, 0.4, Vector::new(1.5, 0.0));
+    });
+}
+
+#[test]
+fn tall_window_letterboxed() {
+    Golden::new("tall_window_letterboxed").size(96, 192).max_mismatched(8).check(|entity_list| {
+        // Fills the bounds, so the bars above and below are all that is left of the background
+        add_polygon(entity_list, 4, 1.5, Vector::new(0.0, 0.0));
+        entity_list.camera_mut().set_aspect_mode(AspectMode::Letterbox);
+    });
+}
+
+// Three quarters of a circle of radius 0.6, newest point first
+fn add_arc_trail(entity_list: &mut EntityList, style: TrailStyle) {
+    let mut trail = Trail::new(entity_list, style);
+    for i in (0..style.length).rev() {
+        let angle = i as f32 / (style.length - 1) as f32 * std::f32::consts::PI * 1.5;
+        trail.push(Vector::new(0.6 * angle.cos(), 0.6 * angle.sin()));
+    }
+    trail.upload(entity_list, None);
+}
+
+#[test]
+fn line_trail() {
+    Golden::new("line_trail").max_mismatched(8).check(|entity_list| {
+        add_arc_trail(entity_list, TrailStyle { length: 48, ..TrailStyle::default() });
+    });
+}
+
+#[test]
+fn ribbon_trail_behind_circle() {
+    Golden::new("ribbon_trail_behind_circle").max_mismatched(8).check(|entity_list| {
+        // Added first but still drawn on top, trails sit on a lower layer
+        add_polygon(entity_list, 24, 0.15, Vector::new(0.6, 0.0));
+        add_arc_trail(entity_list, TrailStyle {
+            length: 48,
+            width: 0.2,
+            head_color: [1.0, 0.5, 0.0, 1.0],
+            tail_color: [0.0, 0.2, 1.0, 0.0],
+        });
+    });
+}
+
+#[test]
+fn vertex_colors() {
+    Golden::new("vertex_colors").max_mismatched(8).check(|entity_list| {
+        // A red, green and blue gradient on the left, a quad of four colors on the right
+        let triangle = VertexData::new(&[Vector::new(-0.9, -0.6), Vector::new(-0.1, -0.6), Vector::new(-0.5, 0.6)])
+            .with_colors(&[[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]]);
+        entity_list.add_entity().set_vertex_data(&triangle, &[0, 1, 2]);
+
+        let quad = VertexData::new(&[Vector::new(0.1, -0.4), Vector::new(0.9, -0.4), Vector::new(0.9, 0.4), Vector::new(0.1, 0.4)])
+            .with_colors(&[[1.0, 1.0, 0.0, 1.0], [0.0, 1.0, 1.0, 1.0], [1.0, 0.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]])
+            .with_uvs(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        entity_list.add_entity().set_vertex_data(&quad, &[0, 1, 2, 0, 2, 3]);
+    });
+}
+
+#[test]
+fn mesh_file() {
+    Golden::new("mesh_file").max_mismatched(8).check(|entity_list| {
+        entity_list.load_mesh(concat!(env!("CARGO_MANIFEST_DIR"), "/meshes/arrow.mesh")).unwrap();
+    });
+}
+
+#[test]
+fn binary_mesh_matches_text_mesh() {
+    // Compared against the reference of `mesh_file`
+    Golden::new("mesh_file").max_mismatched(8).check(|entity_list| {
+        let mesh = Mesh::load(concat!(env!("CARGO_MANIFEST_DIR"), "/meshes/arrow.mesh")).unwrap();
+        let mut bytes = Vec::new();
+        BinaryMesh::from_mesh("arrow", &mesh).write(&mut bytes).unwrap();
+        entity_list.add_entity().set_binary_mesh(&BinaryMesh::from_bytes(bytes).unwrap());
+    });
+}
+
+#[test]
+fn filled_path() {
+    Golden::new("filled_path").max_mismatched(8).check(|entity_list| {
+        // A rounded square with a curvy hole, and a leaf of two cubic curves
+        let badge = Path::new()
+            .move_to(Vector::new(-0.6, -0.9))
+            .line_to(Vector::new(-0.2, -0.9))
+            .arc_to(Vector::new(-0.2, -0.7), PI / 2.0)
+            .line_to(Vector::new(0.0, -0.1))
+            .quad_to(Vector::new(-0.4, 0.1), Vector::new(-0.8, -0.1))
+            .line_to(Vector::new(-0.8, -0.7))
+            .arc_to(Vector::new(-0.6, -0.7), PI / 2.0)
+            .close()
+            .move_to(Vector::new(-0.2, -0.4))
+            .cubic_to(Vector::new(-0.2, -0.1), Vector::new(-0.6, -0.1), Vector::new(-0.6, -0.4))
+            .quad_to(Vector::new(-0.4, -0.8), Vector::new(-0.2, -0.4))
+            .close();
+        entity_list.add_path(&badge);
+
+        let leaf = Path::new()
+            .move_to(Vector::new(0.1, 0.1))
+            .cubic_to(Vector::new(0.2, 0.9), Vector::new(0.9, 0.9), Vector::new(0.9, 0.8))
+            .cubic_to(Vector::new(0.9, 0.3), Vector::new(0.6, 0.0), Vector::new(0.1, 0.1))
+            .close();
+        entity_list.add_path(&leaf);
+    });
+}
@@ -0,0 +1,59 @@
+use renderer::{
+    gpu_nbody::{ GpuBody, GpuNBody },
+    gravity::{ BruteForce, GravityConfig, GravitySolver },
+    integrator::{ Integrator, SymplecticEuler },
+    utils::{ defaults::Float, Vector },
+};
+
+mod common;
+
+// A ring of bodies with a little tangential velocity
+fn ring(count: usize) -> Vec<GpuBody> {
+    (0..count)
+        .map(|i| {
+            let angle = i as Float / count as Float * std::f32::consts::TAU;
+            let (sin, cos) = angle.sin_cos();
+            let radius = 0.3 + 0.2 * (i % 3) as Float;
+            GpuBody::new(
+                1.0 + (i % 5) as Float,
+                0.05,
+                Vector::new(cos * radius, sin * radius),
+                Vector::new(-sin * 0.05, cos * 0.05),
+                [1.0, 1.0, 1.0],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn gpu_steps_match_cpu_steps() {
+    let mut renderer = common::headless(64, 64);
+
+    let bodies = ring(100);
+    let mut nbody = GpuNBody::new(renderer.entities(), &bodies);
+    let config = GravityConfig { softening: 0.02, max_force: Some(0.05), ..Default::default() };
+    nbody.set_config(config);
+    let (dt, steps) = (1.0 / 60.0, 30);
+    nbody.step(dt, steps);
+    let gpu = nbody.read_bodies();
+
+    let masses: Vec<Float> = bodies.iter().map(|body| body.mass).collect();
+    let mut positions: Vec<Vector<Float>> = bodies.iter().map(|body| Vector { pos: body.position }).collect();
+    let mut velocities: Vec<Vector<Float>> = bodies.iter().map(|body| Vector { pos: body.velocity }).collect();
+    for _ in 0..steps {
+        SymplecticEuler.step(&mut positions, &mut velocities, dt, &mut |positions, accelerations| {
+            BruteForce::new(config).accelerations(positions, &masses, accelerations)
+        });
+    }
+
+    assert_eq!(gpu.len(), bodies.len());
+    for (gpu, cpu) in gpu.iter().zip(&positions) {
+        let error = Vector::vec_diff(Vector { pos: gpu.position }, *cpu).mag().sqrt();
+        assert!(error < 1e-4, "GPU and CPU positions are {} apart", error);
+    }
+
+    // The bodies are drawn straight from the storage buffer, white over the grey background
+    let pixels = renderer.render_offscreen();
+    let white = pixels.chunks(4).filter(|pixel| pixel[..3] == [255, 255, 255]).count();
+    assert!(white > 0, "no bodies were drawn");
+}
@@ -0,0 +1,136 @@
+use renderer::{
+    circle::{ Circle, CircleShader },
+    gravity::{ BarnesHut, BruteForce, GravityConfig, GravitySolver },
+    utils::{ defaults::Float, Vector },
+};
+
+mod common;
+
+// Deterministic pseudo random bodies scattered over [-1, 1]², with masses in [1, 10)
+fn scatter(count: usize) -> (Vec<Vector<Float>>, Vec<Float>) {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 40) as Float / (1u64 << 24) as Float
+    };
+
+    let positions = (0..count).map(|_| Vector::new(next() * 2.0 - 1.0, next() * 2.0 - 1.0)).collect();
+    let masses = (0..count).map(|_| 1.0 + next() * 9.0).collect();
+    (positions, masses)
+}
+
+// Errors of the solver's accelerations relative to the exact ones, as (median, largest).
+// The largest one is relative to the mean magnitude instead, bodies whose pulls almost cancel out
+// are way off relative to their own small acceleration
+fn errors(solver: &mut dyn GravitySolver, positions: &[Vector<Float>], masses: &[Float]) -> (Float, Float) {
+    let mut exact = vec![Vector::new(0.0, 0.0); positions.len()];
+    let mut approximate = exact.clone();
+    BruteForce::default().accelerations(positions, masses, &mut exact);
+    solver.accelerations(positions, masses, &mut approximate);
+
+    let differences: Vec<Float> = exact
+        .iter()
+        .zip(&approximate)
+        .map(|(exact, approximate)| Vector::vec_diff(*approximate, *exact).mag().sqrt())
+        .collect();
+    let magnitudes: Vec<Float> = exact.iter().map(|exact| exact.mag().sqrt()).collect();
+
+    let mut relative: Vec<Float> = differences.iter().zip(&magnitudes).map(|(difference, magnitude)| difference / magnitude).collect();
+    relative.sort_by(Float::total_cmp);
+    let mean_magnitude = magnitudes.iter().sum::<Float>() / magnitudes.len() as Float;
+
+    (relative[relative.len() / 2], differences.iter().copied().fold(0.0, Float::max) / mean_magnitude)
+}
+
+#[test]
+fn barnes_hut_matches_brute_force() {
+    let (positions, masses) = scatter(1000);
+    let (median, largest) = errors(&mut BarnesHut::new(0.5), &positions, &masses);
+    assert!(median < 0.02, "median error of {}", median);
+    assert!(largest < 0.05, "errors of up to {} of the mean acceleration", largest);
+}
+
+#[test]
+fn barnes_hut_is_exact_without_opening_angle() {
+    let (positions, masses) = scatter(200);
+    let (_, largest) = errors(&mut BarnesHut::new(0.0), &positions, &masses);
+    assert!(largest < 1e-4, "errors of up to {} of the mean acceleration", largest);
+}
+
+#[test]
+fn barnes_hut_handles_coincident_bodies() {
+    let positions = vec![Vector::new(0.5, 0.5), Vector::new(0.5, 0.5), Vector::new(-0.5, -0.5)];
+    let masses = vec![1.0, 1.0, 1.0];
+    let mut accelerations = vec![Vector::new(0.0, 0.0); 3];
+    BarnesHut::new(0.5).accelerations(&positions, &masses, &mut accelerations);
+    // The pair pulling on each other is undefined, but the lone body is only pulled by their combined mass
+    assert!(accelerations[2].x() > 0.0 && accelerations[2].y() > 0.0);
+}
+
+fn pair_acceleration(config: GravityConfig, distance: Float) -> Float {
+    let positions = [Vector::new(0.0, 0.0), Vector::new(distance, 0.0)];
+    let mut accelerations = [Vector::new(0.0, 0.0); 2];
+    BruteForce::new(config).accelerations(&positions, &[1.0, 1.0], &mut accelerations);
+    accelerations[0].x()
+}
+
+#[test]
+fn plummer_softening() {
+    let config = GravityConfig { g: 1.0, softening: 0.1, ..Default::default() };
+    // r / (r² + ε²)^(3/2)
+    let expected = 0.1 / (0.02 as Float).powf(1.5);
+    assert!((pair_acceleration(config, 0.1) - expected).abs() < 1e-3 * expected);
+    // Stays finite, and falls back to 0 instead of growing without bound
+    assert_eq!(pair_acceleration(config, 0.0), 0.0);
+    assert!(pair_acceleration(config, 0.01) < pair_acceleration(config, 0.1));
+    // Unsoftened far away
+    assert!((pair_acceleration(config, 100.0) - 1e-4).abs() < 1e-8);
+}
+
+#[test]
+fn max_force_and_cutoff() {
+    let clamped = GravityConfig { g: 1.0, max_force: Some(2.0), ..Default::default() };
+    assert!((pair_acceleration(clamped, 0.1) - 2.0).abs() < 1e-5);
+    assert!((pair_acceleration(clamped, 1.0) - 1.0).abs() < 1e-6);
+
+    let cut = GravityConfig { g: 1.0, cutoff: Some(0.5), ..Default::default() };
+    assert_eq!(pair_acceleration(cut, 0.6), 0.0);
+    assert!((pair_acceleration(cut, 0.4) - 6.25).abs() < 1e-4);
+}
+
+#[test]
+fn barnes_hut_uses_its_config() {
+    let (positions, masses) = scatter(200);
+    let config = GravityConfig { softening: 0.05, ..Default::default() };
+    let mut exact = vec![Vector::new(0.0, 0.0); positions.len()];
+    let mut approximate = exact.clone();
+    BruteForce::new(config).accelerations(&positions, &masses, &mut exact);
+    let mut barnes_hut = BarnesHut::new(0.0);
+    barnes_hut.set_config(config);
+    barnes_hut.accelerations(&positions, &masses, &mut approximate);
+
+    for (exact, approximate) in exact.iter().zip(&approximate) {
+        assert!(Vector::vec_diff(*exact, *approximate).mag().sqrt() <= 1e-4 * exact.mag().sqrt().max(1e-6));
+    }
+}
+
+#[test]
+fn circle_gravity_uses_the_solver() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    let zero = Vector::new(0.0, 0.0);
+    let mut circles: Vec<Circle> = [0.0, 0.6]
+        .iter()
+        .map(|&x| Circle::with_shader(entity_list, 1, 0.01, Vector::new(x, 0.0), zero, CircleShader::Base))
+        .collect();
+
+    let cut = GravityConfig { g: 1.0, cutoff: Some(0.5), ..Default::default() };
+    Circle::gravity(&mut circles, &mut BruteForce::new(cut));
+    assert_eq!(circles[0].state().acceleration.pos, [0.0, 0.0]);
+
+    Circle::gravity(&mut circles, &mut BruteForce::new(GravityConfig { g: 1.0, ..Default::default() }));
+    assert!((circles[0].state().acceleration.x() - 1.0 / 0.36).abs() < 1e-4);
+}
@@ -0,0 +1,165 @@
+use renderer::image::{ self, ImageFormat };
+
+#[test]
+fn checksums() {
+    assert_eq!(image::crc32(b"123456789"), 0xCBF43926);
+    assert_eq!(image::crc32(b""), 0);
+    assert_eq!(image::adler32(b"Wikipedia"), 0x11E60398);
+    assert_eq!(image::adler32(b""), 1);
+}
+
+#[test]
+fn format_from_path() {
+    use std::path::Path;
+    assert_eq!(ImageFormat::from_path(Path::new("frame.png")), Some(ImageFormat::Png));
+    assert_eq!(ImageFormat::from_path(Path::new("out/frame.PPM")), Some(ImageFormat::Ppm));
+    assert_eq!(ImageFormat::from_path(Path::new("frame.jpg")), None);
+    assert_eq!(ImageFormat::from_path(Path::new("frame")), None);
+}
+
+#[test]
+fn ppm_round_trip() {
+    let rgba = test_pattern(5, 3);
+    let (width, height, decoded) = image::decode_ppm(&image::encode_ppm(5, 3, &rgba)).unwrap();
+    assert_eq!((width, height), (5, 3));
+    assert_eq!(decoded, rgba);
+}
+
+#[test]
+fn png_round_trip() {
+    // A flat background with a gradient stripe, so both literals and back-references are emitted
+    for (width, height) in [(1, 1), (7, 5), (64, 48)] {
+        let rgba = test_pattern(width, height);
+        let png = image::encode_png(width, height, &rgba);
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']);
+
+        let chunks = chunks(&png[8..]);
+        let kinds: Vec<_> = chunks.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+        let header = chunks[0].1;
+        assert_eq!(&header[0..4], &width.to_be_bytes());
+        assert_eq!(&header[4..8], &height.to_be_bytes());
+        assert_eq!(&header[8..], &[8, 6, 0, 0, 0]);
+
+        let scanlines = zlib_decompress(chunks[1].1);
+        let row_size = (width * 4) as usize;
+        assert_eq!(scanlines.len(), (row_size + 1) * height as usize);
+        for (row, expected) in scanlines.chunks(row_size + 1).zip(rgba.chunks(row_size)) {
+            assert_eq!(row[0], 0, "Scanlines are not filtered");
+            assert_eq!(&row[1..], expected);
+        }
+    }
+}
+
+fn test_pattern(width: u32, height: u32) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            if y == height / 2 {
+                rgba.extend_from_slice(&[(x * 17) as u8, (x * 5 + y) as u8, 200, 255]);
+            } else {
+                rgba.extend_from_slice(&[20, 30, 40, 255]);
+            }
+        }
+    }
+    rgba
+}
+
+// Splits the chunks after the signature, checking the CRC of every one
+fn chunks(mut data: &[u8]) -> Vec<(&[u8; 4], &[u8])> {
+    let mut chunks = Vec::new();
+    while !data.is_empty() {
+        let length = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+        let kind: &[u8; 4] = data[4..8].try_into().unwrap();
+        let body = &data[8..8 + length];
+        let crc = u32::from_be_bytes(data[8 + length..12 + length].try_into().unwrap());
+        assert_eq!(crc, image::crc32(&data[4..8 + length]), "Bad CRC in {:?}", std::str::from_utf8(kind));
+        chunks.push((kind, body));
+        data = &data[12 + length..];
+    }
+    chunks
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let bit = (self.data[self.position / 8] >> (self.position % 8)) & 1;
+            value |= (bit as u32) << i;
+            self.position += 1;
+        }
+        value
+    }
+
+    // Huffman codes are read most significant bit first
+    fn code(&mut self, length: u32) -> u32 {
+        (0..length).fold(0, |code, _| (code << 1) | self.bits(1))
+    }
+
+    fn fixed_symbol(&mut self) -> u32 {
+        let code = self.code(7);
+        if code <= 23 {
+            return 256 + code;
+        }
+        let code = (code << 1) | self.bits(1);
+        match code {
+            0x30..=0xBF => code - 0x30,
+            0xC0..=0xC7 => 280 + code - 0xC0,
+            _ => 144 + ((code << 1) | self.bits(1)) - 0x190,
+        }
+    }
+}
+
+// Inflates a zlib stream made of fixed-huffman blocks, which is all `encode_png` writes
+fn zlib_decompress(data: &[u8]) -> Vec<u8> {
+    const LENGTH_BASES: [u32; 29] = [
+        3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+        35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+    ];
+    const LENGTH_EXTRA_BITS: [u32; 29] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+        3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+    ];
+
+    assert_eq!(data[0] & 0x0F, 8, "Not deflate");
+    assert_eq!(u16::from_be_bytes([data[0], data[1]]) % 31, 0, "Bad zlib header check");
+
+    let mut reader = BitReader { data: &data[2..data.len() - 4], position: 0 };
+    let mut output: Vec<u8> = Vec::new();
+    loop {
+        let last = reader.bits(1) == 1;
+        assert_eq!(reader.bits(2), 1, "Only fixed-huffman blocks are expected");
+        loop {
+            let symbol = reader.fixed_symbol();
+            match symbol {
+                0..=255 => output.push(symbol as u8),
+                256 => break,
+                _ => {
+                    let code = (symbol - 257) as usize;
+                    let length = LENGTH_BASES[code] + reader.bits(LENGTH_EXTRA_BITS[code]);
+                    let distance_code = reader.code(5);
+                    let distance = if distance_code < 4 {
+                        distance_code + 1
+                    } else {
+                        let extra = (distance_code - 2) / 2;
+                        ((2 + (distance_code & 1)) << extra) + 1 + reader.bits(extra)
+                    };
+                    for _ in 0..length {
+                        output.push(output[output.len() - distance as usize]);
+                    }
+                }
+            }
+        }
+        if last { break; }
+    }
+
+    let adler = u32::from_be_bytes(data[data.len() - 4..].try_into().unwrap());
+    assert_eq!(adler, image::adler32(&output), "Bad Adler-32");
+    output
+}
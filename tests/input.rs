@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+
+use renderer::{
+    entity::EntityList,
+    input::InputState,
+    timestep::FrameTime,
+    utils::Vector,
+};
+use winit::{ event::MouseButton, keyboard::KeyCode };
+
+mod common;
+
+#[test]
+fn presses_last_a_frame() {
+    let mut input = InputState::new();
+    input.press_key(KeyCode::Space);
+    // Repeats of a held key are not new presses
+    input.press_key(KeyCode::Space);
+    input.press_button(MouseButton::Left);
+    input.scroll_by(1.0);
+    input.scroll_by(0.5);
+    assert!(input.key_pressed(KeyCode::Space) && input.key_held(KeyCode::Space));
+    assert!(input.button_pressed(MouseButton::Left));
+    assert_eq!(input.scroll_delta(), 1.5);
+
+    input.end_frame();
+    assert!(!input.key_pressed(KeyCode::Space) && input.key_held(KeyCode::Space));
+    assert_eq!(input.scroll_delta(), 0.0);
+
+    // Tapped within a single frame
+    input.press_key(KeyCode::KeyA);
+    input.release_key(KeyCode::KeyA);
+    assert!(input.key_pressed(KeyCode::KeyA) && input.key_released(KeyCode::KeyA) && !input.key_held(KeyCode::KeyA));
+
+    input.release_all();
+    assert!(input.key_released(KeyCode::Space) && input.button_released(MouseButton::Left));
+    assert!(!input.key_held(KeyCode::Space) && !input.button_held(MouseButton::Left));
+    // Nothing to release twice
+    input.end_frame();
+    input.release_key(KeyCode::Space);
+    assert!(!input.key_released(KeyCode::Space));
+}
+
+// Space pressed, Space held and the mouse in the world, as seen by `record_input` on a frame
+type Seen = (bool, bool, Option<Vector<f32>>);
+
+thread_local! {
+    static SEEN: RefCell<Vec<Seen>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record_input(el: &mut EntityList, input: &InputState, _: FrameTime) {
+    let world = input.mouse_world(el.camera());
+    SEEN.with_borrow_mut(|seen| seen.push((input.key_pressed(KeyCode::Space), input.key_held(KeyCode::Space), world)));
+}
+
+#[test]
+fn main_loop_sees_input() {
+    let mut renderer = common::headless(64, 64);
+    renderer.set_main_loop(record_input);
+
+    renderer.input_mut().press_key(KeyCode::Space);
+    renderer.input_mut().set_mouse_position(Some(Vector::new(48.0, 16.0)));
+    renderer.render_offscreen();
+    renderer.render_offscreen();
+
+    let seen = SEEN.take();
+    assert_eq!(seen.len(), 2);
+    // Pressed on the first frame only, held on both
+    assert_eq!((seen[0].0, seen[0].1), (true, true));
+    assert_eq!((seen[1].0, seen[1].1), (false, true));
+    assert_eq!(seen[1].2.unwrap().pos, [0.5, 0.5]);
+}
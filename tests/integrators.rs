@@ -0,0 +1,107 @@
+// Orbits with known solutions, integrated with G = 1
+
+use renderer::{
+    integrator::{ self, Integrator },
+    utils::{ defaults::Float, Vector },
+};
+
+fn gravity(masses: &[Float]) -> impl FnMut(&[Vector<Float>], &mut [Vector<Float>]) + '_ {
+    move |positions, accelerations| {
+        for (i, acceleration) in accelerations.iter_mut().enumerate() {
+            *acceleration = Vector::new(0.0, 0.0);
+            for (j, mass) in masses.iter().enumerate() {
+                if i == j { continue; }
+                let diff = Vector::vec_diff(positions[j], positions[i]);
+                let distance_sq = diff.mag();
+                acceleration.add_vec(Vector::vec_scaled(diff, mass / (distance_sq * distance_sq.sqrt())));
+            }
+        }
+    }
+}
+
+fn energy(positions: &[Vector<Float>], velocities: &[Vector<Float>], masses: &[Float]) -> Float {
+    let mut energy = 0.0;
+    for i in 0..positions.len() {
+        energy += 0.5 * masses[i] * velocities[i].mag();
+        for j in i + 1..positions.len() {
+            energy -= masses[i] * masses[j] / Vector::vec_diff(positions[i], positions[j]).mag().sqrt();
+        }
+    }
+    energy
+}
+
+fn integrate(integrator: &mut dyn Integrator, positions: &mut [Vector<Float>], velocities: &mut [Vector<Float>], masses: &[Float], dt: Float, steps: usize) {
+    let mut acceleration = gravity(masses);
+    for _ in 0..steps {
+        integrator.step(positions, velocities, dt, &mut acceleration);
+    }
+}
+
+// A light body on a circular orbit of radius 1 around a heavy one, period 2 pi
+fn circular_orbit_energy_drift(name: &str) -> Float {
+    let masses = [1.0, 1e-6];
+    let mut positions = [Vector::new(0.0, 0.0), Vector::new(1.0, 0.0)];
+    let mut velocities = [Vector::new(0.0, 0.0), Vector::new(0.0, 1.0)];
+    let initial = energy(&positions, &velocities, &masses);
+
+    let mut integrator = integrator::by_name(name).unwrap();
+    assert_eq!(integrator.name(), name);
+    // 10 orbits at 100 steps each
+    integrate(integrator.as_mut(), &mut positions, &mut velocities, &masses, 2.0 * std::f32::consts::PI / 100.0, 1000);
+
+    ((energy(&positions, &velocities, &masses) - initial) / initial).abs()
+}
+
+#[test]
+fn higher_order_integrators_conserve_energy() {
+    for name in ["velocity_verlet", "rk4", "rk45"] {
+        let drift = circular_orbit_energy_drift(name);
+        assert!(drift < 1e-3, "{} drifted by {}", name, drift);
+    }
+}
+
+#[test]
+fn explicit_euler_gains_energy() {
+    let euler = circular_orbit_energy_drift("explicit_euler");
+    let symplectic = circular_orbit_energy_drift("symplectic_euler");
+    assert!(euler > 0.1, "explicit euler only drifted by {}", euler);
+    assert!(symplectic < euler / 10.0, "symplectic euler drifted by {}", symplectic);
+}
+
+// Chenciner and Montgomery's figure-eight choreography
+#[test]
+fn figure_eight_returns_after_one_period() {
+    const PERIOD: Float = 6.325_913;
+    let masses = [1.0, 1.0, 1.0];
+    let start_positions = [Vector::new(-0.970_004_4, 0.243_087_53), Vector::new(0.970_004_4, -0.243_087_53), Vector::new(0.0, 0.0)];
+    let start_velocities = [Vector::new(0.466_203_7, 0.432_365_73), Vector::new(0.466_203_7, 0.432_365_73), Vector::new(-0.932_407_4, -0.864_731_46)];
+
+    for name in ["velocity_verlet", "rk4", "rk45"] {
+        let (mut positions, mut velocities) = (start_positions, start_velocities);
+        let mut integrator = integrator::by_name(name).unwrap();
+        integrate(integrator.as_mut(), &mut positions, &mut velocities, &masses, PERIOD / 2000.0, 2000);
+
+        for (position, start) in positions.iter().zip(&start_positions) {
+            let error = Vector::vec_diff(*position, *start).mag().sqrt();
+            assert!(error < 1e-2, "{} ended {} away from the start", name, error);
+        }
+    }
+}
+
+#[test]
+fn rk45_rejects_substeps_that_blow_up() {
+    // No acceleration up to x = 0.5, undefined past it
+    let mut acceleration = |positions: &[Vector<Float>], accelerations: &mut [Vector<Float>]| {
+        for (position, acceleration) in positions.iter().zip(accelerations.iter_mut()) {
+            *acceleration = if position.x() > 0.5 { Vector::new(Float::NAN, 0.0) } else { Vector::new(0.0, 0.0) };
+        }
+    };
+
+    let mut positions = [Vector::new(0.0, 0.0)];
+    let mut velocities = [Vector::new(1.0, 0.0)];
+    integrator::RungeKutta45::default().step(&mut positions, &mut velocities, 1.0, &mut acceleration);
+
+    // Stopped short of the undefined region instead of carrying NaNs out of it
+    assert!(positions[0].x().is_finite() && velocities[0].x().is_finite());
+    assert!(positions[0].x() > 0.4 && positions[0].x() <= 0.5, "stopped at {}", positions[0].x());
+}
@@ -0,0 +1,130 @@
+use winit::event::MouseButton;
+use renderer::{
+    circle::Circle,
+    collision::CollisionResponse,
+    input::InputState,
+    interaction::Interaction,
+    scene::Scene,
+    utils::Vector,
+};
+
+mod common;
+
+fn close(a: Vector<f32>, b: Vector<f32>) -> bool {
+    Vector::vec_diff(a, b).mag().sqrt() < 1e-4
+}
+
+#[test]
+fn pick_drag_fling_and_spawn() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    let scene = Scene::parse("
+        spawn mass=3 radius=0.05
+        body mass=1 radius=0.1 position=0,0
+        body mass=1 radius=0.1 position=0.15,0
+    ").unwrap();
+    let mut circles = scene.spawn(entity_list);
+
+    // Hits go by radius, the closest center wins where circles overlap
+    assert_eq!(Circle::pick(&circles, Vector::new(0.05, 0.05)), Some(0));
+    assert_eq!(Circle::pick(&circles, Vector::new(0.09, 0.0)), Some(1));
+    assert_eq!(Circle::pick(&circles, Vector::new(0.0, 0.2)), None);
+
+    // Grabbed off center, the circle keeps its offset to the cursor
+    let mut interaction = Interaction::new(scene.spawn, None);
+    assert!(interaction.grab_or_spawn(&mut circles, entity_list, Vector::new(0.05, 0.0)));
+    assert_eq!(interaction.grabbed(), Some(circles[0].entity_index()));
+    interaction.drag_by(&mut circles, entity_list, Vector::new(0.0, 0.5));
+    assert!(close(circles[0].position(), Vector::new(0.0, 0.5)));
+
+    // Dragged at a steady 1 unit per second, longer than the fling window
+    for _ in 0..20 {
+        interaction.drag_by(&mut circles, entity_list, Vector::new(0.01, 0.0));
+        interaction.hold(&mut circles, entity_list, 0.01);
+    }
+    assert!(close(circles[0].position(), Vector::new(0.2, 0.5)));
+    interaction.release(&mut circles, entity_list);
+    assert_eq!(interaction.grabbed(), None);
+    assert!(close(circles[0].velocity(), Vector::new(1.0, 0.0)));
+
+    // Empty space spawns a copy of the template, held still until dragged
+    interaction.set_spawn_mass(7);
+    assert!(!interaction.grab_or_spawn(&mut circles, entity_list, Vector::new(-0.5, -0.5)));
+    assert_eq!(circles.len(), 3);
+    assert_eq!((circles[2].mass(), circles[2].radius()), (7, 0.05));
+    assert!(close(circles[2].position(), Vector::new(-0.5, -0.5)));
+    interaction.release(&mut circles, entity_list);
+    assert!(close(circles[2].velocity(), Vector::new(0.0, 0.0)));
+}
+
+#[test]
+fn mouse_grabs_drags_and_flings() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    let scene = Scene::parse("body mass=1 radius=0.1 position=0,0").unwrap();
+    let mut circles = scene.spawn(entity_list);
+    let mut interaction = Interaction::new(scene.spawn, None);
+    let mut input = InputState::new();
+
+    // Pressed just off center
+    input.set_mouse_position(Some(entity_list.camera().world_to_screen(Vector::new(0.02, 0.0))));
+    input.press_button(MouseButton::Left);
+    interaction.update(&mut circles, entity_list, &input);
+    assert_eq!(interaction.grabbed(), Some(circles[0].entity_index()));
+    input.end_frame();
+
+    // Moved at a steady 1 unit per second
+    for i in 1..=20 {
+        let cursor = Vector::new(0.02 + i as f32 * 0.01, 0.0);
+        input.set_mouse_position(Some(entity_list.camera().world_to_screen(cursor)));
+        interaction.update(&mut circles, entity_list, &input);
+        interaction.hold(&mut circles, entity_list, 0.01);
+        input.end_frame();
+    }
+    assert!(close(circles[0].position(), Vector::new(0.2, 0.0)));
+
+    input.release_button(MouseButton::Left);
+    interaction.update(&mut circles, entity_list, &input);
+    input.end_frame();
+    assert_eq!(interaction.grabbed(), None);
+    assert!(close(circles[0].velocity(), Vector::new(1.0, 0.0)));
+
+    // Leaving the window lets go even though the button was never seen going up
+    input.set_mouse_position(Some(entity_list.camera().world_to_screen(Vector::new(0.2, 0.0))));
+    input.press_button(MouseButton::Left);
+    interaction.update(&mut circles, entity_list, &input);
+    input.end_frame();
+    assert!(interaction.grabbed().is_some());
+    input.set_mouse_position(None);
+    interaction.update(&mut circles, entity_list, &input);
+    assert_eq!(interaction.grabbed(), None);
+}
+
+#[test]
+fn grab_ends_when_the_circle_is_merged_away() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    let scene = Scene::parse("
+        body mass=3 radius=0.1 position=-0.05,0
+        body mass=1 radius=0.1 position=0.05,0
+    ").unwrap();
+    let mut circles = scene.spawn(entity_list);
+    let mut interaction = Interaction::new(scene.spawn, None);
+    interaction.grab_or_spawn(&mut circles, entity_list, Vector::new(0.1, 0.0));
+    let grabbed = circles[1].entity_index();
+    assert_eq!(interaction.grabbed(), Some(grabbed));
+
+    // The grabbed circle is absorbed, and a new one spawned elsewhere reuses its entity
+    Circle::collide(&mut circles, entity_list, CollisionResponse::Merge);
+    circles.push(scene.spawn.spawn(entity_list, None));
+    assert_eq!(circles[1].entity_index(), grabbed);
+    let position = circles[1].position();
+
+    interaction.drag_by(&mut circles, entity_list, Vector::new(0.5, 0.5));
+    interaction.hold(&mut circles, entity_list, 0.01);
+    assert_eq!(interaction.grabbed(), None);
+    assert!(close(circles[1].position(), position));
+}
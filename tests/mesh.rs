@@ -0,0 +1,60 @@
+use renderer::mesh::{ Mesh, MeshError };
+
+fn parse_error(source: &str) -> (usize, usize, String) {
+    match Mesh::parse(source) {
+        Err(MeshError::Parse { line, column, message }) => (line, column, message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn bundled_mesh_loads() {
+    let mesh = Mesh::load(concat!(env!("CARGO_MANIFEST_DIR"), "/meshes/arrow.mesh")).unwrap();
+    assert_eq!(mesh.vertices.len(), 7);
+    assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3, 4, 5, 6]);
+    assert_eq!(mesh.vertices[5].position, [0.8, 0.0]);
+    assert_eq!(mesh.vertices[5].color, [1.0, 0.6, 0.1, 1.0]);
+}
+
+#[test]
+#[allow(deprecated)]
+fn vertex_new_forwards_to_mesh() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/meshes/arrow.mesh");
+    let mesh = Mesh::load(path).unwrap();
+    assert_eq!(renderer::vertex::Vertex::new(path.to_string()), (mesh.vertices, mesh.indices));
+}
+
+#[test]
+fn optional_parts() {
+    let mesh = Mesh::parse("
+        0,0        # white, as no color is given
+        1,0,5 1,0,0,0.5
+
+        0,1\t0,0,1
+    ").unwrap();
+    // Without a `~` the vertices are used in order
+    assert_eq!(mesh.indices, [0, 1, 2]);
+    assert_eq!(mesh.vertices[0].color, [1.0; 4]);
+    // The third coordinate is dropped
+    assert_eq!(mesh.vertices[1].position, [1.0, 0.0]);
+    assert_eq!(mesh.vertices[1].color, [1.0, 0.0, 0.0, 0.5]);
+    assert_eq!(mesh.vertices[2].color, [0.0, 0.0, 1.0, 1.0]);
+}
+
+#[test]
+fn errors_point_at_line_and_column() {
+    assert_eq!(parse_error("0,0\n  0,x"), (2, 5, "`x` is not a number".to_string()));
+    assert_eq!(parse_error("0,0 1,1"), (1, 5, "Expected at least 3 numbers in `1,1`".to_string()));
+    assert_eq!(parse_error("0,0,0,0").2, "Expected at most 3 numbers in `0,0,0,0`");
+    assert_eq!(parse_error("0,0 1,1,1 extra").1, 11);
+    assert_eq!(parse_error("0,0\n1,0\n0,1\n~\n0 1 3").1, 5);
+    assert_eq!(parse_error("0,0\n1,0\n0,1\n~\n0 1 3").2, "Index 3 is out of range for 3 vertices");
+    assert_eq!(parse_error("0,0\n1,0\n0,1\n~\n0 1 -1").2, "`-1` is not an index");
+    // Points just past the last index
+    assert_eq!(parse_error("0,0\n1,0\n0,1\n~\n0 1 2\n2 1"), (6, 4, "5 indices don't make whole triangles".to_string()));
+    assert_eq!(parse_error("0,0\n1,0\n# one short").2, "2 vertices don't make whole triangles");
+    assert_eq!(parse_error("0,0\n~\n~").0, 3);
+    // Columns count characters, the ideographic space takes 3 bytes
+    assert_eq!(parse_error("\u{3000}0,x"), (1, 4, "`x` is not a number".to_string()));
+    assert_eq!(parse_error("0,0\n1,0\n0,1\n~\n\u{3000}0 1"), (5, 5, "2 indices don't make whole triangles".to_string()));
+}
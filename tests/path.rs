@@ -0,0 +1,154 @@
+use renderer::{
+    path::Path,
+    triangulate::triangles_area,
+    utils::{
+        defaults::{ Float, PI },
+        Vector
+    },
+};
+
+fn filled_area(path: &Path) -> Float {
+    let (vertices, indices) = path.fill();
+    triangles_area(&vertices, &indices)
+}
+
+#[test]
+fn lines_fill_polygons() {
+    let square = Path::new()
+        .move_to(Vector::new(0.0, 0.0))
+        .line_to(Vector::new(1.0, 0.0))
+        .line_to(Vector::new(1.0, 1.0))
+        .line_to(Vector::new(0.0, 1.0))
+        .close();
+    assert_eq!(square.contours().len(), 1);
+    assert_eq!(square.current(), Vector::new(0.0, 0.0));
+    assert!((filled_area(&square) - 1.0).abs() < 1e-6);
+
+    // Explicitly returning to the start and winding clockwise make no difference
+    let clockwise = Path::new()
+        .move_to(Vector::new(0.0, 0.0))
+        .line_to(Vector::new(0.0, 1.0))
+        .line_to(Vector::new(1.0, 1.0))
+        .line_to(Vector::new(1.0, 0.0))
+        .line_to(Vector::new(0.0, 0.0));
+    assert_eq!(clockwise.fill().1.len(), 6);
+    assert!((filled_area(&clockwise) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn arcs_stay_within_tolerance() {
+    let tolerance = 0.01;
+    let circle = Path::with_tolerance(tolerance)
+        .move_to(Vector::new(1.0, 0.0))
+        .arc_to(Vector::new(0.0, 0.0), 2.0 * PI)
+        .close();
+
+    let points = &circle.contours()[0];
+    for (i, &point) in points.iter().enumerate() {
+        let next = points[(i + 1) % points.len()];
+        assert!((point.mag().sqrt() - 1.0).abs() < 1e-5);
+        let middle = Vector::vec_scaled(Vector::vec_sum(point, next), 0.5);
+        assert!(1.0 - middle.mag().sqrt() <= tolerance + 1e-5);
+    }
+
+    let area = filled_area(&circle);
+    assert!(area < PI && PI - area < 2.0 * PI * tolerance, "{}", area);
+
+    // Finer tolerances take more points
+    let finer = Path::with_tolerance(tolerance / 10.0)
+        .move_to(Vector::new(1.0, 0.0))
+        .arc_to(Vector::new(0.0, 0.0), 2.0 * PI);
+    assert!(finer.contours()[0].len() > points.len() * 2);
+}
+
+#[test]
+fn arcs_turn_both_ways() {
+    let counter_clockwise = Path::new().move_to(Vector::new(1.0, 0.0)).arc_to(Vector::new(0.0, 0.0), PI / 2.0);
+    let end = counter_clockwise.current();
+    assert!(end.x().abs() < 1e-6 && (end.y() - 1.0).abs() < 1e-6);
+
+    let clockwise = Path::new().move_to(Vector::new(1.0, 0.0)).arc_to(Vector::new(0.0, 0.0), -PI / 2.0);
+    let end = clockwise.current();
+    assert!(end.x().abs() < 1e-6 && (end.y() + 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn quadratic_curves() {
+    let tolerance = 0.001;
+    // The area between a parabola and its chord is two thirds of the control triangle's
+    let path = Path::with_tolerance(tolerance)
+        .move_to(Vector::new(0.0, 0.0))
+        .quad_to(Vector::new(1.0, 2.0), Vector::new(2.0, 0.0))
+        .close();
+    assert_eq!(path.current(), Vector::new(0.0, 0.0));
+    let area = filled_area(&path);
+    let exact = 4.0 / 3.0;
+    assert!(area <= exact + 1e-5 && exact - area < 3.0 * tolerance, "{}", area);
+}
+
+#[test]
+fn cubic_curves() {
+    let tolerance = 0.001;
+    // x = 3t² - 2t³ and y = 3t(1 - t), enclosing 18 ∫ t²(1 - t)² dt = 0.6 with the x axis
+    let path = Path::with_tolerance(tolerance)
+        .move_to(Vector::new(0.0, 0.0))
+        .cubic_to(Vector::new(0.0, 1.0), Vector::new(1.0, 1.0), Vector::new(1.0, 0.0))
+        .close();
+    let points = &path.contours()[0];
+    assert_eq!(points.last(), Some(&Vector::new(1.0, 0.0)));
+    let area = filled_area(&path);
+    assert!(area <= 0.6 + 1e-5 && 0.6 - area < 3.0 * tolerance, "{}", area);
+
+    let coarse = Path::with_tolerance(0.1)
+        .move_to(Vector::new(0.0, 0.0))
+        .cubic_to(Vector::new(0.0, 1.0), Vector::new(1.0, 1.0), Vector::new(1.0, 0.0));
+    assert!(coarse.contours()[0].len() < points.len());
+}
+
+#[test]
+fn inner_contours_are_holes() {
+    let radius = 0.5;
+    let ring = Path::new()
+        .move_to(Vector::new(-1.0, -1.0))
+        .line_to(Vector::new(1.0, -1.0))
+        .line_to(Vector::new(1.0, 1.0))
+        .line_to(Vector::new(-1.0, 1.0))
+        .close()
+        .move_to(Vector::new(radius, 0.0))
+        .arc_to(Vector::new(0.0, 0.0), 2.0 * PI)
+        .close()
+        // An island inside the hole is filled again
+        .move_to(Vector::new(-0.1, -0.1))
+        .line_to(Vector::new(0.1, -0.1))
+        .line_to(Vector::new(0.1, 0.1))
+        .line_to(Vector::new(-0.1, 0.1))
+        .close();
+    assert_eq!(ring.contours().len(), 3);
+
+    let area = filled_area(&ring);
+    let exact = 4.0 - PI * radius * radius + 0.04;
+    assert!((area - exact).abs() < 0.01, "{}", area);
+}
+
+#[test]
+fn segments_after_close_start_a_new_contour() {
+    let path = Path::new()
+        .move_to(Vector::new(0.0, 0.0))
+        .line_to(Vector::new(1.0, 0.0))
+        .line_to(Vector::new(0.0, 1.0))
+        .close()
+        .line_to(Vector::new(-1.0, 0.0))
+        .line_to(Vector::new(0.0, -1.0));
+    assert_eq!(path.contours().len(), 2);
+    assert_eq!(path.contours()[1], [Vector::new(0.0, 0.0), Vector::new(-1.0, 0.0), Vector::new(0.0, -1.0)]);
+    assert!((filled_area(&path) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn nothing_to_fill() {
+    let (vertices, indices) = Path::new().fill();
+    assert!(vertices.is_empty() && indices.is_empty());
+
+    let line = Path::new().move_to(Vector::new(0.0, 0.0)).line_to(Vector::new(1.0, 1.0));
+    assert!(line.fill().1.is_empty());
+}
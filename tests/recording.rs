@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use renderer::{
+    image::{ self, ImageFormat },
+    recording::{ Recorder, Recording },
+};
+
+fn output_directory(name: &str) -> PathBuf {
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+#[test]
+fn every_nth_frame_is_written_in_order() {
+    let directory = output_directory("recording-every-nth");
+    let mut recorder = Recorder::start(Recording::image_sequence(&directory, ImageFormat::Ppm).every_nth_frame(3)).unwrap();
+
+    let mut wanted = Vec::new();
+    for frame in 0..8u8 {
+        wanted.push(recorder.wants_frame());
+        // Unwanted frames don't have to be captured at all
+        let rgba = [frame, 0, 0, 255];
+        recorder.record(1, 1, recorder.wants_frame().then_some(&rgba[..])).unwrap();
+    }
+    assert_eq!(wanted, [true, false, false, true, false, false, true, false]);
+    assert_eq!(recorder.finish().unwrap(), 3);
+
+    // Numbered by frames written, not frames rendered
+    let mut files: Vec<String> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["frame-000000.ppm", "frame-000001.ppm", "frame-000002.ppm"]);
+
+    let (_, _, rgba) = image::decode_ppm(&std::fs::read(directory.join("frame-000002.ppm")).unwrap()).unwrap();
+    assert_eq!(rgba, [6, 0, 0, 255]);
+}
+
+#[test]
+fn every_nth_frame_is_at_least_one() {
+    assert_eq!(Recording::image_sequence("frames", ImageFormat::Png).every_nth_frame(0).every_nth_frame, 1);
+}
+
+#[test]
+fn ffmpeg_command_reads_raw_frames_from_stdin() {
+    let command = Recording::ffmpeg_command(320, 240, 60, "out.mp4");
+    assert_eq!(command.get_program(), "ffmpeg");
+    let args: Vec<&str> = command.get_args().map(|arg| arg.to_str().unwrap()).collect();
+    assert_eq!(args, [
+        "-y", "-f", "rawvideo", "-pixel_format", "rgba",
+        "-video_size", "320x240",
+        "-framerate", "60",
+        "-i", "-", "-pix_fmt", "yuv420p",
+        "out.mp4",
+    ]);
+}
@@ -0,0 +1,85 @@
+use renderer::{
+    circle::CircleShader,
+    collision::CollisionResponse,
+    scene::{ Scene, SceneError, SolverKind },
+};
+
+fn parse_error(source: &str) -> (usize, String) {
+    match Scene::parse(source) {
+        Err(SceneError::Parse { line, message }) => (line, message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn bundled_scene_loads() {
+    let scene = Scene::load(concat!(env!("CARGO_MANIFEST_DIR"), "/scenes/three_bodies.scene")).unwrap();
+    assert_eq!(scene.bodies.len(), 3);
+    assert_eq!(scene.bodies[1].shader, CircleShader::Base);
+    assert_eq!(scene.bodies[2].shader, CircleShader::Color([0.6, 0.4, 0.1]));
+    assert_eq!(scene.collisions, CollisionResponse::Elastic { restitution: 0.9 });
+    assert_eq!(scene.integrator().name(), "velocity_verlet");
+}
+
+#[test]
+fn every_setting() {
+    let scene = Scene::parse("
+        # Everything set
+        g 1.5
+        softening 0.1   # trailing comment
+        max_force 3
+        cutoff 2
+        timestep 0.01
+        max_substeps 4
+        integrator rk4
+        solver barnes_hut 0.7
+        collisions merge
+        trails 60 0.01
+        spawn mass=5 radius=0.05 color=1,1,1
+
+        body mass=2 radius=0.1 position=1,-1 velocity=0.5,0
+    ").unwrap();
+
+    assert_eq!(scene.gravity.g, 1.5);
+    assert_eq!(scene.gravity.softening, 0.1);
+    assert_eq!(scene.gravity.max_force, Some(3.0));
+    assert_eq!(scene.gravity.cutoff, Some(2.0));
+    assert_eq!((scene.timestep, scene.max_substeps), (0.01, 4));
+    assert_eq!(scene.integrator, "rk4");
+    assert_eq!(scene.solver, SolverKind::BarnesHut(0.7));
+    assert_eq!(scene.collisions, CollisionResponse::Merge);
+    assert_eq!(scene.solver().config().g, 1.5);
+    let trails = scene.trails.unwrap();
+    assert_eq!((trails.length, trails.width), (60, 0.01));
+    assert_eq!((scene.spawn.mass, scene.spawn.radius), (5, 0.05));
+    assert_eq!(scene.spawn.shader, CircleShader::Color([1.0, 1.0, 1.0]));
+
+    let body = scene.bodies[0];
+    assert_eq!((body.mass, body.radius, body.position, body.velocity), (2, 0.1, [1.0, -1.0], [0.5, 0.0]));
+    assert_eq!(body.shader, CircleShader::Base);
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    assert_eq!(parse_error("g 1\n\nfrobnicate 2").0, 3);
+    assert_eq!(parse_error("integrator leapfrog").1, "Unknown integrator `leapfrog`");
+    assert_eq!(parse_error("body radius=0.1").1, "Bodies need a `mass`");
+    assert_eq!(parse_error("body mass=1 radius=0.1 position=1,2,3").1, "`1,2,3` should have 2 components");
+    assert_eq!(parse_error("body mass=1 radius=0.1 shader=color").1, "The color shader needs a `color`");
+    assert_eq!(parse_error("timestep 0").1, "The timestep has to be a positive number");
+    assert_eq!(parse_error("timestep NaN").1, "The timestep has to be a positive number");
+    assert_eq!(parse_error("softening").1, "`softening` takes one argument");
+}
+
+#[test]
+fn rejects_degenerate_values() {
+    assert_eq!(parse_error("g 1\nbody mass=0 radius=0.1"), (2, "The mass has to be positive".to_string()));
+    assert_eq!(parse_error("body mass=1 radius=0").1, "The radius has to be a positive number");
+    assert_eq!(parse_error("spawn mass=1 radius=-0.1").1, "The radius has to be a positive number");
+    assert_eq!(parse_error("body mass=1 radius=NaN").1, "The radius has to be a positive number");
+    for theta in ["-0.5", "inf", "NaN"] {
+        let (line, message) = parse_error(&format!("\n\nsolver barnes_hut {}", theta));
+        assert_eq!((line, message.as_str()), (3, "The opening angle has to be a finite number, zero or above"));
+    }
+    assert!(Scene::parse("solver barnes_hut 0").is_ok());
+}
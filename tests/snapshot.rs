@@ -0,0 +1,117 @@
+use renderer::{
+    circle::{ Circle, CircleShader, CircleState },
+    entity::EntityList,
+    gravity::{ BruteForce, GravityConfig },
+    integrator::VelocityVerlet,
+    snapshot::{ Snapshot, SnapshotError },
+    trail::TrailStyle,
+    utils::Vector,
+};
+
+mod common;
+
+const DT: f32 = 1.0 / 60.0;
+
+fn config() -> GravityConfig {
+    GravityConfig { softening: 0.01, max_force: Some(0.5), ..Default::default() }
+}
+
+fn run(circles: &mut [Circle], entity_list: &mut EntityList, steps: u32) {
+    let mut solver = BruteForce::new(config());
+    for _ in 0..steps {
+        Circle::step(circles, entity_list, &mut VelocityVerlet, &mut solver, DT);
+    }
+}
+
+fn example() -> Snapshot {
+    let state = |x: f32, shader| CircleState {
+        mass: 3,
+        radius: 0.05,
+        position: Vector::new(x, 0.25),
+        previous_position: Vector::new(x, 0.2),
+        velocity: Vector::new(0.1, -0.0),
+        acceleration: Vector::new(f32::MIN_POSITIVE, 1e-30),
+        shader,
+    };
+    Snapshot {
+        steps: 1234,
+        time: 20.566_666_666_7,
+        dt: DT,
+        gravity: config(),
+        circles: vec![state(0.1, CircleShader::Base), state(-0.3, CircleShader::Color([0.2, 0.4, 0.6]))],
+    }
+}
+
+#[test]
+fn round_trips_exactly() {
+    let snapshot = example();
+    let mut bytes = Vec::new();
+    snapshot.write(&mut bytes).unwrap();
+    assert_eq!(Snapshot::read(&bytes[..]).unwrap(), snapshot);
+}
+
+#[test]
+fn rejects_other_files() {
+    let mut bytes = Vec::new();
+    example().write(&mut bytes).unwrap();
+
+    assert!(matches!(Snapshot::read(&b"P6\n1 1\n255\n"[..]), Err(SnapshotError::NotASnapshot)));
+
+    let mut newer = bytes.clone();
+    newer[8..12].copy_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(Snapshot::read(&newer[..]), Err(SnapshotError::UnsupportedVersion(2))));
+
+    assert!(matches!(Snapshot::read(&bytes[..bytes.len() - 1]), Err(SnapshotError::Corrupt(_))));
+    bytes.push(0);
+    assert!(matches!(Snapshot::read(&bytes[..]), Err(SnapshotError::Corrupt(_))));
+}
+
+#[test]
+fn resumed_run_follows_the_same_trajectory() {
+    // A single renderer, the GL backend doesn't cope with several devices at once
+    let mut renderer = common::headless(16, 16);
+
+    let mut circles: Vec<Circle> = (0..6)
+        .map(|i| {
+            let angle = i as f32;
+            let position = Vector::new(angle.cos() * 0.4, angle.sin() * 0.4);
+            let velocity = Vector::new(-angle.sin() * 0.02, angle.cos() * 0.02);
+            Circle::with_shader(renderer.entities(), 1 + i, 0.02, position, velocity, CircleShader::Base)
+        })
+        .collect();
+    run(&mut circles, renderer.entities(), 50);
+
+    let mut bytes = Vec::new();
+    Snapshot::capture(&circles, 50, 50.0 * DT as f64, DT, config()).write(&mut bytes).unwrap();
+    let snapshot = Snapshot::read(&bytes[..]).unwrap();
+    assert_eq!((snapshot.steps, snapshot.gravity), (50, config()));
+
+    let mut restored = snapshot.restore(renderer.entities());
+    run(&mut circles, renderer.entities(), 100);
+    run(&mut restored, renderer.entities(), 100);
+
+    let states: Vec<CircleState> = circles.iter().map(Circle::state).collect();
+    let restored_states: Vec<CircleState> = restored.iter().map(Circle::state).collect();
+    assert_eq!(states, restored_states);
+}
+
+#[test]
+fn restores_bodies_and_gravity_only() {
+    let mut renderer = common::headless(16, 16);
+    let entity_list = renderer.entities();
+
+    let mut circles = vec![Circle::with_shader(entity_list, 2, 0.05, Vector::new(0.1, 0.0), Vector::new(0.0, 0.1), CircleShader::Base)];
+    circles[0].set_trail(entity_list, TrailStyle::default());
+    run(&mut circles, entity_list, 10);
+    assert!(!circles[0].trail().unwrap().is_empty());
+
+    // Everything a snapshot holds, a field added here has to be written, read and checked too
+    let snapshot = Snapshot::capture(&circles, 10, 10.0 * DT as f64, DT, config());
+    let Snapshot { steps, time, dt, gravity, circles: _ } = snapshot.clone();
+    assert_eq!((steps, time, dt, gravity), (10, 10.0 * DT as f64, DT, config()));
+
+    let restored = snapshot.restore(entity_list);
+    assert_eq!(restored[0].state(), circles[0].state());
+    // Trails aren't stored
+    assert!(restored[0].trail().is_none());
+}
@@ -0,0 +1,113 @@
+use renderer::{ timestep::FixedTimestep, utils::defaults::Float };
+
+#[test]
+fn accumulates_whole_steps() {
+    let mut timestep = FixedTimestep::new(0.1, 8);
+    // Too short for a step, carried over to the next frame
+    let frame = timestep.advance(0.05);
+    assert_eq!(frame.steps, 0);
+    assert!((frame.alpha - 0.5).abs() < 1e-5);
+
+    let frame = timestep.advance(0.08);
+    assert_eq!(frame.steps, 1);
+    assert!((frame.alpha - 0.3).abs() < 1e-4);
+    assert!((frame.time - 0.1).abs() < 1e-6);
+    assert!((frame.dt - 0.1).abs() < 1e-6);
+
+    let frame = timestep.advance(0.25);
+    assert_eq!(frame.steps, 2);
+    assert!((frame.alpha - 0.8).abs() < 1e-4);
+    assert!((timestep.time() - 0.3).abs() < 1e-6);
+}
+
+#[test]
+fn clamps_substeps() {
+    let mut timestep = FixedTimestep::new(0.1, 3);
+    // A long hitch only takes `max_substeps` steps, and the backlog beyond the last fraction of a step is dropped
+    let frame = timestep.advance(1.05);
+    assert_eq!(frame.steps, 3);
+    assert!((frame.alpha - 0.5).abs() < 1e-4);
+    assert!((frame.time - 0.3).abs() < 1e-6);
+
+    let frame = timestep.advance(0.06);
+    assert_eq!(frame.steps, 1);
+    assert!((frame.alpha - 0.1).abs() < 1e-4);
+
+    timestep.set_max_substeps(0);
+    assert_eq!(timestep.max_substeps(), 1);
+    assert_eq!(timestep.advance(10.0).steps, 1);
+}
+
+#[test]
+fn fixed_steps_ignore_real_time() {
+    let mut timestep = FixedTimestep::new(0.1, 2);
+    timestep.advance(0.05);
+    // More than `max_substeps`, and the accumulated fraction is dropped
+    let frame = timestep.advance_steps(5);
+    assert_eq!(frame.steps, 5);
+    assert_eq!(frame.alpha, 0.0);
+    assert_eq!(timestep.advance(0.05).steps, 0);
+}
+
+#[test]
+fn pause_and_single_step() {
+    let mut timestep = FixedTimestep::new(0.1, 8);
+    let frame = timestep.advance(0.25);
+    assert_eq!(frame.steps, 2);
+
+    timestep.set_paused(true);
+    let paused = timestep.advance(1.0);
+    assert_eq!(paused.steps, 0);
+    // Drawn where the last frame left off
+    assert!((paused.alpha - frame.alpha).abs() < 1e-5);
+    assert!((paused.time - frame.time).abs() < 1e-9);
+
+    timestep.step_once();
+    timestep.step_once();
+    assert_eq!(timestep.advance(0.0).steps, 2);
+    assert_eq!(timestep.advance(1.0).steps, 0);
+    assert!((timestep.time() - 0.4).abs() < 1e-6);
+
+    // Stepping while running pauses
+    timestep.set_paused(false);
+    timestep.step_once();
+    assert!(timestep.paused());
+    assert_eq!(timestep.advance_steps(5).steps, 1);
+    assert_eq!(timestep.advance_steps(5).steps, 0);
+}
+
+#[test]
+fn time_scale() {
+    let mut timestep = FixedTimestep::new(0.1, 2);
+    timestep.set_time_scale(4.0);
+    // Up to 2 substeps times 4
+    assert_eq!(timestep.advance(0.2).steps, 8);
+    assert_eq!(timestep.advance(1.0).steps, 8);
+
+    timestep.set_time_scale(0.5);
+    let steps: u32 = (0..4).map(|_| timestep.advance_steps(1).steps).sum();
+    assert_eq!(steps, 2);
+    timestep.set_time_scale(2.0);
+    assert_eq!(timestep.advance_steps(1).steps, 2);
+
+    timestep.set_time_scale(1e6);
+    assert_eq!(timestep.time_scale(), FixedTimestep::MAX_TIME_SCALE);
+    timestep.set_time_scale(Float::NAN);
+    assert_eq!(timestep.time_scale(), FixedTimestep::MAX_TIME_SCALE);
+}
+
+#[test]
+fn invalid_steps_are_ignored() {
+    let mut timestep = FixedTimestep::new(0.1, 8);
+    for dt in [0.0, -0.1, Float::NAN, Float::INFINITY] {
+        timestep.set_dt(dt);
+        assert_eq!(timestep.dt(), 0.1);
+    }
+    assert_eq!(timestep.advance(0.25).steps, 2);
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn new_rejects_a_zero_step() {
+    FixedTimestep::new(0.0, 8);
+}
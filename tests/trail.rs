@@ -0,0 +1,47 @@
+use renderer::{
+    circle::Circle,
+    gravity::BruteForce,
+    integrator::SymplecticEuler,
+    scene::Scene,
+    trail::{ Trail, TrailStyle, TRAIL_LAYER },
+    utils::Vector,
+};
+
+mod common;
+
+#[test]
+fn trails() {
+    let mut renderer = common::headless(64, 64);
+    let entity_list = renderer.entities();
+
+    // The ring buffer keeps the newest `length` points, newest first
+    let mut trail = Trail::new(entity_list, TrailStyle { length: 3, ..TrailStyle::default() });
+    assert_eq!(entity_list.get_entity_unchecked(trail.entity_index()).layer(), TRAIL_LAYER);
+    assert!(trail.is_empty());
+    for x in 0..5 {
+        trail.push(Vector::new(x as f32, 0.0));
+    }
+    let xs: Vec<f32> = trail.points().map(|point| point.x()).collect();
+    assert_eq!(xs, [4.0, 3.0, 2.0]);
+    // Uploading with a head draws one more point than is kept
+    trail.upload(entity_list, Some(Vector::new(5.0, 0.0)));
+    assert_eq!(trail.len(), 3);
+    trail.clear();
+    assert!(trail.is_empty());
+
+    // Circles record where they have been once they step past it
+    let scene = Scene::parse("trails 4 0.01\nbody mass=1 radius=0.02 position=0,0 velocity=1,0 color=1,0,0").unwrap();
+    let mut circles = scene.spawn(entity_list);
+    let style = *circles[0].trail().unwrap().style();
+    assert_eq!((style.length, style.width), (4, 0.01));
+    assert_eq!(style.head_color[..3], [1.0, 0.0, 0.0]);
+
+    let mut integrator = SymplecticEuler;
+    let mut solver = BruteForce::default();
+    for _ in 0..6 {
+        Circle::step(&mut circles, entity_list, &mut integrator, &mut solver, 0.5);
+    }
+    Circle::interpolate(&mut circles, entity_list, 0.5);
+    let xs: Vec<f32> = circles[0].trail().unwrap().points().map(|point| point.x()).collect();
+    assert_eq!(xs, [2.5, 2.0, 1.5, 1.0]);
+}
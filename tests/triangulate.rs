@@ -0,0 +1,106 @@
+use renderer::{
+    triangulate::{ signed_area, triangles_area, triangulate, triangulate_with_holes },
+    utils::{
+        defaults::{ Float, Index },
+        Vector
+    },
+};
+
+fn points(coordinates: &[(Float, Float)]) -> Vec<Vector<Float>> {
+    coordinates.iter().map(|&(x, y)| Vector::new(x, y)).collect()
+}
+
+// Every triangle wound counter-clockwise and together exactly as large as the polygon
+fn assert_fills(vertices: &[Vector<Float>], indices: &[Index], area: Float, triangles: usize) {
+    assert_eq!(indices.len(), triangles * 3, "{:?}", indices);
+    for triangle in indices.chunks_exact(3) {
+        let single: Vec<Vector<Float>> = triangle.iter().map(|&index| vertices[index as usize]).collect();
+        assert!(signed_area(&single) > 0.0, "{:?} is not counter-clockwise", triangle);
+    }
+    let total = triangles_area(vertices, indices);
+    assert!((total - area).abs() < 1e-5, "triangles cover {}, the polygon {}", total, area);
+}
+
+#[test]
+fn signed_area_follows_winding() {
+    let square = points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+    assert_eq!(signed_area(&square), 4.0);
+    let reversed: Vec<Vector<Float>> = square.into_iter().rev().collect();
+    assert_eq!(signed_area(&reversed), -4.0);
+}
+
+#[test]
+fn convex_polygon() {
+    let square = points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+    assert_fills(&square, &triangulate(&square), 1.0, 2);
+}
+
+#[test]
+fn concave_polygon() {
+    let l_shape = points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]);
+    assert_fills(&l_shape, &triangulate(&l_shape), 3.0, 4);
+
+    // Teeth pointing up, every other vertex is reflex
+    let comb = points(&[(0.0, 0.0), (5.0, 0.0), (5.0, 2.0), (4.0, 1.0), (3.0, 2.0), (2.0, 1.0), (1.0, 2.0), (0.0, 1.0)]);
+    assert_fills(&comb, &triangulate(&comb), signed_area(&comb), 6);
+}
+
+#[test]
+fn clockwise_polygon() {
+    // A five pointed star, drawn clockwise
+    let star: Vec<Vector<Float>> = (0..10)
+        .map(|i| {
+            let angle = -(i as Float) * std::f32::consts::PI / 5.0;
+            let radius = if i % 2 == 0 { 1.0 } else { 0.4 };
+            Vector::new(angle.cos() * radius, angle.sin() * radius)
+        })
+        .collect();
+    assert!(signed_area(&star) < 0.0);
+    assert_fills(&star, &triangulate(&star), -signed_area(&star), 8);
+}
+
+#[test]
+fn collinear_vertices_make_no_flat_triangles() {
+    let square = points(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+    let indices = triangulate(&square);
+    assert_fills(&square, &indices, 4.0, 3);
+}
+
+#[test]
+fn too_few_points() {
+    assert!(triangulate(&points(&[(0.0, 0.0), (1.0, 0.0)])).is_empty());
+}
+
+#[test]
+fn polygon_with_hole() {
+    let square = points(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+    // Wound the same way as the outline, which doesn't matter
+    let hole = points(&[(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]);
+    let (vertices, indices) = triangulate_with_holes(&square, &[&hole]);
+    assert_eq!(vertices.len(), 8);
+    assert_eq!(&vertices[4..], &hole[..]);
+    assert_fills(&vertices, &indices, 12.0, 8);
+}
+
+#[test]
+fn concave_polygon_with_holes() {
+    let l_shape = points(&[(0.0, 0.0), (6.0, 0.0), (6.0, 2.0), (2.0, 2.0), (2.0, 6.0), (0.0, 6.0)]);
+    let right = points(&[(4.0, 0.5), (4.0, 1.5), (5.0, 1.5), (5.0, 0.5)]);
+    let top = points(&[(0.5, 4.0), (1.5, 4.0), (1.0, 5.0)]);
+    let middle = points(&[(0.5, 0.5), (1.5, 0.5), (1.5, 1.5), (0.5, 1.5)]);
+    let (vertices, indices) = triangulate_with_holes(&l_shape, &[&right, &top, &middle]);
+
+    let area = signed_area(&l_shape) - signed_area(&right).abs() - signed_area(&top).abs() - signed_area(&middle).abs();
+    // Each hole adds its vertex count and two bridging triangles
+    assert_fills(&vertices, &indices, area, 6 - 2 + (4 + 2) + (3 + 2) + (4 + 2));
+}
+
+#[test]
+fn holes_behind_each_other() {
+    // The left hole can only be bridged once the right one is joined, its view to the right is blocked
+    let outline = points(&[(0.0, 0.0), (10.0, 0.0), (10.0, 2.0), (0.0, 2.0)]);
+    let left = points(&[(1.0, 0.5), (3.0, 0.5), (3.0, 1.5), (1.0, 1.5)]);
+    let right = points(&[(5.0, 0.25), (7.0, 0.25), (7.0, 1.75), (5.0, 1.75)]);
+    let (vertices, indices) = triangulate_with_holes(&outline, &[&left, &right]);
+    assert_fills(&vertices, &indices, 20.0 - 2.0 - 3.0, 2 + 6 + 6);
+}
@@ -0,0 +1,50 @@
+use renderer::{
+    utils::Vector,
+    vertex::{ Attribute, Vertex, VertexData, VertexFormat },
+};
+
+#[test]
+fn formats_lay_attributes_out_in_order() {
+    let format = VertexFormat::new(&[
+        Attribute::Position,
+        Attribute::Uv,
+        Attribute::Custom(wgpu::VertexFormat::Uint32),
+        Attribute::Color,
+        Attribute::Custom(wgpu::VertexFormat::Float32),
+    ]);
+    assert_eq!(format.stride(), 8 + 8 + 4 + 16 + 4);
+
+    // Locations depend on the kind, offsets on the order
+    let layout = format.desc();
+    let placed: Vec<(u32, u64)> = layout.attributes.iter().map(|attribute| (attribute.shader_location, attribute.offset)).collect();
+    assert_eq!(placed, [(0, 0), (2, 8), (3, 16), (1, 20), (4, 36)]);
+
+    assert_eq!(VertexFormat::position().stride(), 8);
+    assert_eq!(VertexFormat::position_color().desc().array_stride, Vertex::desc().array_stride);
+}
+
+#[test]
+#[should_panic(expected = "twice")]
+fn attributes_other_than_custom_ones_are_unique() {
+    VertexFormat::new(&[Attribute::Position, Attribute::Color, Attribute::Color]);
+}
+
+#[test]
+fn vertex_data_interleaves() {
+    let data = VertexData::new(&[Vector::new(1.0, 2.0), Vector::new(3.0, 4.0)])
+        .with_colors(&[[0.1, 0.2, 0.3, 1.0], [0.4, 0.5, 0.6, 1.0]])
+        .with_custom(wgpu::VertexFormat::Uint32, &[7u32, 8]);
+    assert_eq!(data.format(), VertexFormat::new(&[Attribute::Position, Attribute::Color, Attribute::Custom(wgpu::VertexFormat::Uint32)]));
+
+    let bytes = data.bytes();
+    assert_eq!(bytes.len(), 2 * 28);
+    let float = |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let uint = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    assert_eq!((float(0), float(4), float(8), float(20), uint(24)), (1.0, 2.0, 0.1, 1.0, 7));
+    assert_eq!((float(28), float(32), float(36), uint(52)), (3.0, 4.0, 0.4, 8));
+
+    let vertices = [Vertex { position: [1.0, 2.0], color: [0.1, 0.2, 0.3, 1.0] }];
+    let from_vertices = Vertex::to_vertex_data(&vertices);
+    assert_eq!(from_vertices.format(), VertexFormat::position_color());
+    assert_eq!(from_vertices.colors(), Some(&[[0.1, 0.2, 0.3, 1.0]][..]));
+}